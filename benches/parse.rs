@@ -0,0 +1,33 @@
+use criterion::{Criterion, criterion_group, criterion_main, black_box};
+use src_ctx::{SourceMap, Origin};
+use treelang::{Indent, Tree, borrowed};
+
+
+fn large_input() -> String {
+    let mut content = String::new();
+    for index in 0..2000 {
+        content.push_str(&format!("server-{}: (address localhost) [port {}]\n", index, index));
+        content.push_str("  enabled true\n");
+        content.push_str("  route: /api/v1\n");
+        content.push_str("    handler some-module/some-function {timeout 2.5}\n");
+    }
+    content
+}
+
+fn parse(c: &mut Criterion) {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("bench"), large_input().into())
+        .try_into_inserted().unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("owned", |b| {
+        b.iter(|| Tree::parse(black_box(map.input(index)), Indent::spaces(2)).unwrap())
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| borrowed::Tree::parse(black_box(map.input(index)), Indent::spaces(2)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
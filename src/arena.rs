@@ -0,0 +1,207 @@
+//! An arena representation of a [`Tree`] with parent links.
+//!
+//! Nodes are stored in pre-order and identified by a [`NodeId`], which can be used as an index
+//! into side tables of the same length as the arena.
+use src_ctx::Offset;
+
+use crate::{Tree, Node, NodeKind, Directive, Statement, Item, Doc, Comment};
+
+
+/// Identifies a node in a [`TreeArena`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// The pre-order position of the node in its arena.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// A node in a [`TreeArena`], without its children.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArenaNode {
+    pub kind: ArenaNodeKind,
+    pub location: Offset,
+    pub docs: Vec<Doc>,
+    pub comment: Option<Comment>,
+    parent: Option<NodeId>,
+    position: usize,
+    children: Vec<NodeId>,
+}
+
+impl ArenaNode {
+    /// The signature independent of the [`ArenaNodeKind`].
+    pub fn signature(&self) -> &[Item] {
+        match &self.kind {
+            ArenaNodeKind::Directive { signature, .. } => signature,
+            ArenaNodeKind::Statement { signature } => signature,
+        }
+    }
+
+    /// Returns `true` if the node is a directive.
+    pub fn is_directive(&self) -> bool {
+        matches!(self.kind, ArenaNodeKind::Directive { .. })
+    }
+}
+
+/// The different kinds of [`ArenaNode`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArenaNodeKind {
+    Directive { signature: Vec<Item>, arguments: Vec<Item> },
+    Statement { signature: Vec<Item> },
+}
+
+/// A [`Tree`] stored as a flat list of nodes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TreeArena {
+    nodes: Vec<ArenaNode>,
+    roots: Vec<NodeId>,
+}
+
+impl From<&Tree> for TreeArena {
+    fn from(tree: &Tree) -> Self {
+        let mut arena = Self { nodes: Vec::new(), roots: Vec::new() };
+        arena.roots = arena.insert_all(tree, None);
+        arena
+    }
+}
+
+impl TreeArena {
+    /// Build an arena from a [`Tree`].
+    pub fn new(tree: &Tree) -> Self {
+        Self::from(tree)
+    }
+
+    /// The number of nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the arena contains no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// All node IDs in pre-order.
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    /// The root nodes.
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// The node with the given ID, if it exists in this arena.
+    pub fn get(&self, id: NodeId) -> Option<&ArenaNode> {
+        self.nodes.get(id.0)
+    }
+
+    /// The parent of a node, or [`None`] for roots.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self[id].parent
+    }
+
+    /// The children of a node.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self[id].children
+    }
+
+    /// The parent, grandparent and so on up to the root.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent(id), |&id| self.parent(id))
+    }
+
+    /// The number of ancestors.
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.ancestors(id).count()
+    }
+
+    /// The node and its siblings, in order.
+    pub fn siblings(&self, id: NodeId) -> &[NodeId] {
+        match self.parent(id) {
+            Some(parent) => self.children(parent),
+            None => &self.roots,
+        }
+    }
+
+    /// The sibling following a node.
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.siblings(id).get(self[id].position + 1).copied()
+    }
+
+    /// The sibling preceding a node.
+    pub fn previous_sibling(&self, id: NodeId) -> Option<NodeId> {
+        let position = self[id].position.checked_sub(1)?;
+        self.siblings(id).get(position).copied()
+    }
+
+    /// Convert a node and its descendants back into an owned [`Node`].
+    pub fn to_node(&self, id: NodeId) -> Node {
+        let node = &self[id];
+        let kind = match &node.kind {
+            ArenaNodeKind::Directive { signature, arguments } => NodeKind::Directive(Directive {
+                signature: signature.clone(),
+                arguments: arguments.clone(),
+                children: node.children.iter().map(|&child| self.to_node(child)).collect(),
+            }),
+            ArenaNodeKind::Statement { signature } => NodeKind::Statement(Statement {
+                signature: signature.clone(),
+            }),
+        };
+        Node {
+            kind,
+            location: node.location,
+            docs: node.docs.clone(),
+            comment: node.comment.clone(),
+        }
+    }
+
+    /// Convert the arena back into an owned [`Tree`].
+    pub fn to_tree(&self) -> Tree {
+        Tree { roots: self.roots.iter().map(|&root| self.to_node(root)).collect() }
+    }
+
+    fn insert_all(&mut self, nodes: &[Node], parent: Option<NodeId>) -> Vec<NodeId> {
+        nodes.iter().enumerate()
+            .map(|(position, node)| self.insert(node, parent, position))
+            .collect()
+    }
+
+    fn insert(&mut self, node: &Node, parent: Option<NodeId>, position: usize) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        let (kind, children) = match &node.kind {
+            NodeKind::Directive(directive) => {
+                let kind = ArenaNodeKind::Directive {
+                    signature: directive.signature.clone(),
+                    arguments: directive.arguments.clone(),
+                };
+                (kind, &directive.children[..])
+            },
+            NodeKind::Statement(statement) => {
+                (ArenaNodeKind::Statement { signature: statement.signature.clone() }, &[][..])
+            },
+        };
+        self.nodes.push(ArenaNode {
+            kind,
+            location: node.location,
+            docs: node.docs.clone(),
+            comment: node.comment.clone(),
+            parent,
+            position,
+            children: Vec::new(),
+        });
+        let children = self.insert_all(children, Some(id));
+        self.nodes[id.0].children = children;
+        id
+    }
+}
+
+impl std::ops::Index<NodeId> for TreeArena {
+    type Output = ArenaNode;
+
+    fn index(&self, id: NodeId) -> &Self::Output {
+        &self.nodes[id.0]
+    }
+}
@@ -0,0 +1,31 @@
+//! Helpers shared by the `treelang` and `treelang-lsp` binaries.
+use treelang::ItemKind;
+
+
+/// The number of doc comment lines, or [`None`] if the source contains other comments.
+pub fn doc_lines(source: &str) -> Option<usize> {
+    let mut count = 0;
+    for line in source.lines().filter(|line| line.contains(';')) {
+        if !line.trim_start().starts_with(";;") {
+            return None;
+        }
+        count += 1;
+    }
+    Some(count)
+}
+
+/// The name of an item kind as shown to users.
+pub fn item_kind_name(kind: &ItemKind) -> &'static str {
+    match kind {
+        ItemKind::Word(_) => "word",
+        ItemKind::Int(_) => "int",
+        ItemKind::Float(_) => "float",
+        ItemKind::Bool(_) => "bool",
+        ItemKind::Null => "null",
+        ItemKind::Sigil { .. } => "sigil",
+        ItemKind::Parentheses(_) => "parentheses",
+        ItemKind::Brackets(_) => "brackets",
+        ItemKind::Braces(_) => "braces",
+        ItemKind::Pair { .. } => "pair",
+    }
+}
@@ -0,0 +1,492 @@
+//! Language server for treelang documents, communicating over stdio.
+//!
+//! The indentation can be configured with the `indent` initialization option, which is
+//! either `"tabs"` or a number of spaces. Two spaces are assumed by default.
+use std::collections::HashMap;
+use std::error::Error;
+
+use lsp_server::{Connection, Message, Notification, Request, Response, ErrorCode};
+use lsp_types::notification::{
+    Notification as _, DidOpenTextDocument, DidChangeTextDocument, DidCloseTextDocument,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Request as _, DocumentSymbolRequest, FoldingRangeRequest, Formatting, HoverRequest,
+};
+use lsp_types::{
+    Url, Position, Range, Diagnostic, DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse,
+    SymbolKind, FoldingRange, TextEdit, Hover, HoverContents, MarkupContent, MarkupKind,
+    InitializeParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    OneOf, FoldingRangeProviderCapability, HoverProviderCapability, PublishDiagnosticsParams,
+    DidOpenTextDocumentParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DiagnosticRelatedInformation, Location,
+};
+use src_ctx::{SourceMap, Origin};
+use treelang::{
+    Tree, Node, NodeKind, Item, ItemKind, Indent, Locator, ParseOptions, ParseResult, Literals,
+};
+
+use self::shared::{doc_lines, item_kind_name};
+
+mod shared;
+
+
+type ServerResult<T = ()> = Result<T, Box<dyn Error + Send + Sync>>;
+
+fn main() -> ServerResult {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    })?;
+    let params: InitializeParams = serde_json::from_value(connection.initialize(capabilities)?)?;
+    let indent = indent_option(params.initialization_options.as_ref());
+    let server = Server {
+        indent,
+        options: parse_options(params.initialization_options.as_ref(), indent),
+        documents: HashMap::new(),
+    };
+    server.run(connection)?;
+    io_threads.join()?;
+    Ok(())
+}
+
+fn indent_option(options: Option<&serde_json::Value>) -> Indent {
+    let indent = options.and_then(|options| options.get("indent"));
+    if indent.and_then(|indent| indent.as_str()) == Some("tabs") {
+        Indent::tabs()
+    } else {
+        indent
+            .and_then(|indent| indent.as_u64())
+            .and_then(|count| u8::try_from(count).ok())
+            .and_then(Indent::try_spaces)
+            .unwrap_or(Indent::spaces(2))
+    }
+}
+
+/// Options for parsing with literals, sigils and a pair separator, ignoring invalid values.
+fn parse_options(options: Option<&serde_json::Value>, indent: Indent) -> ParseOptions {
+    let mut parse_options = ParseOptions::new(indent);
+    let Some(options) = options else {
+        return parse_options;
+    };
+    match options.get("literals") {
+        Some(serde_json::Value::Bool(true)) => {
+            parse_options = parse_options.literals(Literals::DEFAULT);
+        },
+        Some(literals @ serde_json::Value::Object(_)) => {
+            let words = |key| leak_words(literals.get(key));
+            let literals = Literals::new(words("true"), words("false"), words("null"));
+            parse_options = parse_options.literals(literals);
+        },
+        _ => {},
+    }
+    if let Some(sigils) = options.get("sigils").and_then(|sigils| sigils.as_str()) {
+        let sigils = sigils.chars().collect::<Vec<_>>().leak();
+        parse_options = parse_options.try_sigils(sigils).unwrap_or(parse_options);
+    }
+    let mut separator = options.get("pairSeparator")
+        .and_then(|separator| separator.as_str())
+        .unwrap_or("")
+        .chars();
+    if let (Some(separator), None) = (separator.next(), separator.next()) {
+        parse_options = parse_options.try_pair_separator(separator).unwrap_or(parse_options);
+    }
+    parse_options
+}
+
+/// Leak the strings of an array, since the options are created once and live until exit.
+fn leak_words(words: Option<&serde_json::Value>) -> &'static [&'static str] {
+    let words = words.and_then(|words| words.as_array()).map_or(&[][..], |words| words);
+    words.iter()
+        .filter_map(|word| word.as_str())
+        .map(|word| &*String::from(word).leak())
+        .collect::<Vec<_>>()
+        .leak()
+}
+
+struct Server {
+    indent: Indent,
+    options: ParseOptions,
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    fn run(mut self, connection: Connection) -> ServerResult {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    connection.sender.send(Message::Response(response))?;
+                },
+                Message::Notification(notification) => {
+                    let method = notification.method.clone();
+                    match self.handle_notification(notification) {
+                        Ok(Some(published)) => {
+                            connection.sender.send(Message::Notification(published))?;
+                        },
+                        Ok(None) => {},
+                        // Notifications cannot be answered, so invalid ones are only logged.
+                        Err(error) => {
+                            eprintln!("ignoring invalid `{}` notification: {}", method, error);
+                        },
+                    }
+                },
+                Message::Response(_) => {},
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        match request.method.as_str() {
+            DocumentSymbolRequest::METHOD => respond::<DocumentSymbolRequest, _>(request, |params| {
+                self.analyze(&params.text_document.uri, |analysis| {
+                    let symbols = analysis.document_symbols();
+                    DocumentSymbolResponse::Nested(symbols)
+                })
+            }),
+            FoldingRangeRequest::METHOD => respond::<FoldingRangeRequest, _>(request, |params| {
+                self.analyze(&params.text_document.uri, |analysis| analysis.folding_ranges())
+            }),
+            Formatting::METHOD => respond::<Formatting, _>(request, |params| {
+                self.analyze(&params.text_document.uri, |analysis| analysis.formatting())
+                    .flatten()
+            }),
+            HoverRequest::METHOD => respond::<HoverRequest, _>(request, |params| {
+                let position = params.text_document_position_params;
+                self.analyze(&position.text_document.uri, |analysis| {
+                    analysis.hover(position.position)
+                }).flatten()
+            }),
+            _ => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{}`", request.method),
+            ),
+        }
+    }
+
+    fn handle_notification(
+        &mut self,
+        notification: Notification,
+    ) -> ServerResult<Option<Notification>> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(notification.params)?;
+                let document = params.text_document;
+                self.documents.insert(document.uri.clone(), document.text);
+                document.uri
+            },
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = serde_json::from_value(notification.params)?;
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri.clone(), change.text);
+                }
+                params.text_document.uri
+            },
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                params.text_document.uri
+            },
+            _ => return Ok(None),
+        };
+        let diagnostics = self.analyze(&uri, |analysis| analysis.diagnostics(&uri))
+            .unwrap_or_default();
+        let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+        Ok(Some(Notification::new(PublishDiagnostics::METHOD.into(), params)))
+    }
+
+    fn analyze<F, R>(&self, uri: &Url, analyze: F) -> Option<R>
+    where
+        F: FnOnce(&mut Analysis<'_>) -> R,
+    {
+        let text = self.documents.get(uri)?;
+        let mut map = SourceMap::new();
+        let index = map.insert(Origin::from_named(uri.as_str()), text.as_str().into())
+            .try_into_inserted()
+            .expect("single source should not conflict");
+        let input = map.input(index);
+        let mut analysis = Analysis {
+            result: Tree::parse_with(input.clone(), self.options),
+            locator: Locator::new(input),
+            lines: LineIndex::new(text),
+            indent: self.indent,
+        };
+        Some(analyze(&mut analysis))
+    }
+}
+
+fn respond<R, F>(request: Request, handle: F) -> Response
+where
+    R: lsp_types::request::Request,
+    F: FnOnce(R::Params) -> R::Result,
+{
+    match serde_json::from_value::<R::Params>(request.params) {
+        Ok(params) => Response::new_ok(request.id, handle(params)),
+        Err(error) => Response::new_err(
+            request.id,
+            ErrorCode::InvalidParams as i32,
+            error.to_string(),
+        ),
+    }
+}
+
+struct Analysis<'a> {
+    result: ParseResult<Tree>,
+    locator: Locator<'a>,
+    lines: LineIndex<'a>,
+    indent: Indent,
+}
+
+impl<'a> Analysis<'a> {
+    fn diagnostics(&mut self, uri: &Url) -> Vec<Diagnostic> {
+        let Err(error) = &self.result else {
+            return Vec::new();
+        };
+        let index = self.locator.byte_index(error.offset()).unwrap_or(0);
+        let related = error.context().iter()
+            .filter_map(|&offset| self.locator.byte_index(offset))
+            .map(|index| DiagnosticRelatedInformation {
+                location: Location::new(uri.clone(), self.lines.token_range(index)),
+                message: "related location".into(),
+            })
+            .collect::<Vec<_>>();
+        vec![Diagnostic {
+            range: self.lines.token_range(index),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("treelang".into()),
+            message: error.error().to_string(),
+            related_information: Some(related).filter(|related| !related.is_empty()),
+            ..Diagnostic::default()
+        }]
+    }
+
+    fn document_symbols(&mut self) -> Vec<DocumentSymbol> {
+        let Ok(tree) = &self.result else {
+            return Vec::new();
+        };
+        let mut locator = self.locator.clone();
+        tree.roots.iter()
+            .filter_map(|node| symbol(node, &mut locator, &self.lines))
+            .collect()
+    }
+
+    fn folding_ranges(&mut self) -> Vec<FoldingRange> {
+        let mut ranges = Vec::new();
+        if let Ok(tree) = &self.result {
+            let mut locator = self.locator.clone();
+            for node in &tree.roots {
+                collect_folding_ranges(node, &mut locator, &self.lines, &mut ranges);
+            }
+        }
+        ranges
+    }
+
+    /// Edits reformatting the whole document.
+    ///
+    /// Like `treelang fmt`, documents containing comments other than doc comments attached to
+    /// nodes or an `__END__` marker are left alone, since the tree does not preserve them.
+    fn formatting(&mut self) -> Option<Vec<TextEdit>> {
+        let text = self.lines.text;
+        if text.lines().any(|line| line.trim() == "__END__") {
+            return None;
+        }
+        let tree = self.result.as_ref().ok()?;
+        let formatted = tree.to_source(self.indent);
+        if doc_lines(text).is_none() || doc_lines(text) != doc_lines(&formatted) {
+            return None;
+        }
+        if formatted == self.lines.text {
+            return Some(Vec::new());
+        }
+        let end = self.lines.position(self.lines.text.len());
+        let range = Range::new(Position::new(0, 0), end);
+        Some(vec![TextEdit::new(range, formatted)])
+    }
+
+    fn hover(&mut self, position: Position) -> Option<Hover> {
+        let tree = self.result.as_ref().ok()?;
+        let index = self.lines.byte_index(position);
+        let mut locator = self.locator.clone();
+        let (node, item) = tree.roots.iter()
+            .find_map(|node| find_node_item(node, index, &mut locator))?;
+        let range = locator.byte_range(item.location)?;
+        let mut value = format!("{} `{}`", item_kind_name(&item.kind), item);
+        if !node.docs.is_empty() {
+            value.push_str("\n\n");
+            let docs: Vec<&str> = node.docs.iter().map(|doc| doc.text.as_str()).collect();
+            value.push_str(&docs.join("\n"));
+        }
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(Range::new(
+                self.lines.position(range.start),
+                self.lines.position(range.end),
+            )),
+        })
+    }
+}
+
+#[allow(deprecated)]
+fn symbol(node: &Node, locator: &mut Locator<'_>, lines: &LineIndex<'_>) -> Option<DocumentSymbol> {
+    let directive = node.directive()?;
+    let start = locator.byte_index(node.location)?;
+    let signature_end = locator.byte_index(directive.signature.last()?.location.end())?;
+    let end = node_end(node, locator, lines)?;
+    let children = directive.children.iter()
+        .filter_map(|child| symbol(child, locator, lines))
+        .collect::<Vec<_>>();
+    Some(DocumentSymbol {
+        name: items_text(&directive.signature),
+        detail: Some(items_text(&directive.arguments)).filter(|detail| !detail.is_empty()),
+        kind: SymbolKind::NAMESPACE,
+        tags: None,
+        deprecated: None,
+        range: Range::new(lines.position(start), lines.position(end)),
+        selection_range: Range::new(lines.position(start), lines.position(signature_end)),
+        children: Some(children).filter(|children| !children.is_empty()),
+    })
+}
+
+fn collect_folding_ranges(
+    node: &Node,
+    locator: &mut Locator<'_>,
+    lines: &LineIndex<'_>,
+    ranges: &mut Vec<FoldingRange>,
+) -> Option<()> {
+    if node.children().is_empty() {
+        return Some(());
+    }
+    let start = locator.byte_index(node.location)?;
+    let end = node_end(node, locator, lines)?;
+    ranges.push(FoldingRange {
+        start_line: lines.position(start).line,
+        end_line: lines.position(end).line,
+        ..FoldingRange::default()
+    });
+    for child in node.children() {
+        collect_folding_ranges(child, locator, lines, ranges)?;
+    }
+    Some(())
+}
+
+/// Find the end of the last line belonging to the node or its descendants.
+fn node_end(node: &Node, locator: &mut Locator<'_>, lines: &LineIndex<'_>) -> Option<usize> {
+    let mut last = node;
+    while let Some(child) = last.children().last() {
+        last = child;
+    }
+    let start = locator.byte_index(last.location)?;
+    Some(lines.line_end(start))
+}
+
+/// Find the item at `index` together with the node containing it.
+fn find_node_item<'t>(
+    node: &'t Node,
+    index: usize,
+    locator: &mut Locator<'_>,
+) -> Option<(&'t Node, &'t Item)> {
+    let found = match &node.kind {
+        NodeKind::Directive(directive) => find_item(&directive.signature, index, locator)
+            .or_else(|| find_item(&directive.arguments, index, locator)),
+        NodeKind::Statement(statement) => find_item(&statement.signature, index, locator),
+    };
+    found.map(|item| (node, item)).or_else(|| {
+        node.children().iter().find_map(|child| find_node_item(child, index, locator))
+    })
+}
+
+fn find_item<'t>(items: &'t [Item], index: usize, locator: &mut Locator<'_>) -> Option<&'t Item> {
+    for item in items {
+        // The key of a pair is inside of the pair's location, so nested items are found first.
+        let nested = match &item.kind {
+            ItemKind::Parentheses(items) | ItemKind::Brackets(items) | ItemKind::Braces(items) => {
+                find_item(items, index, locator)
+            },
+            ItemKind::Pair { key, value, .. } => {
+                find_item(std::slice::from_ref(key), index, locator)
+                    .or_else(|| find_item(std::slice::from_ref(value), index, locator))
+            },
+            _ => None,
+        };
+        if nested.is_some() {
+            return nested;
+        }
+        let range = locator.byte_range(item.location)?;
+        if range.contains(&index) {
+            return Some(item);
+        }
+    }
+    None
+}
+
+fn items_text(items: &[Item]) -> String {
+    items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// Converts between byte indices and LSP positions with UTF-16 columns.
+struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(text: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self { text, starts }
+    }
+
+    fn line_of(&self, index: usize) -> usize {
+        self.starts.partition_point(|&start| start <= index) - 1
+    }
+
+    fn position(&self, index: usize) -> Position {
+        let line = self.line_of(index);
+        let column = self.text[self.starts[line]..index].encode_utf16().count();
+        Position::new(line as u32, column as u32)
+    }
+
+    fn line_end(&self, index: usize) -> usize {
+        self.text[index..].find('\n').map_or(self.text.len(), |end| index + end)
+    }
+
+    /// The range of the non-whitespace text starting at `index`, or of the single character at
+    /// `index` if it is whitespace other than a line break.
+    fn token_range(&self, index: usize) -> Range {
+        let rest = &self.text[index..self.line_end(index)];
+        let end = match rest.find(char::is_whitespace) {
+            Some(0) => rest.chars().next().map_or(0, char::len_utf8),
+            Some(end) => end,
+            None => rest.len(),
+        };
+        Range::new(self.position(index), self.position(index + end))
+    }
+
+    fn byte_index(&self, position: Position) -> usize {
+        let Some(&start) = self.starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let mut column = 0;
+        for (index, c) in self.text[start..].char_indices() {
+            if column >= position.character as usize || c == '\n' {
+                return start + index;
+            }
+            column += c.len_utf16();
+        }
+        self.text.len()
+    }
+}
@@ -0,0 +1,372 @@
+//! Command-line tool to check, format and dump treelang files.
+use std::fmt::Write as _;
+use std::process::ExitCode;
+
+use src_ctx::{SourceMap, Origin, SourceError};
+use treelang::{Tree, Node, NodeKind, Item, ItemKind, Indent, Locator, ParseError};
+
+use self::shared::{doc_lines, item_kind_name};
+
+mod shared;
+
+
+const USAGE: &str = "\
+Usage: treelang [--indent tabs|<spaces>] <command> [options] <files>...
+
+Commands:
+    check               Parse the files and report errors
+    fmt [--check]       Reformat the files in place, or only check their formatting
+    dump [--json]       Print the parsed tree of the files
+
+The indentation defaults to two spaces.
+";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match Options::parse(&args) {
+        Ok(options) => options.run(),
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            ExitCode::from(2)
+        },
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Check,
+    Format { check: bool },
+    Dump { json: bool },
+}
+
+struct Options {
+    indent: Indent,
+    command: Command,
+    files: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut indent = Indent::spaces(2);
+        let mut command = None;
+        let mut check = false;
+        let mut json = false;
+        let mut files = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--indent" => {
+                    let value = args.next().ok_or("missing value for `--indent`")?;
+                    indent = parse_indent(value)
+                        .ok_or_else(|| format!("invalid indentation `{}`", value))?;
+                },
+                "--check" => check = true,
+                "--json" => json = true,
+                option if option.starts_with("--") => {
+                    return Err(format!("unknown option `{}`", option));
+                },
+                name if command.is_none() => command = Some(name),
+                file => files.push(file.into()),
+            }
+        }
+        let command = match command.ok_or("missing command")? {
+            "check" if !check && !json => Command::Check,
+            "fmt" if !json => Command::Format { check },
+            "dump" if !check => Command::Dump { json },
+            "check" | "fmt" | "dump" => return Err("option not supported by command".into()),
+            unknown => return Err(format!("unknown command `{}`", unknown)),
+        };
+        if files.is_empty() {
+            return Err("no files given".into());
+        }
+        Ok(Self { indent, command, files })
+    }
+
+    fn run(&self) -> ExitCode {
+        let mut success = true;
+        for path in &self.files {
+            let content = match std::fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(error) => {
+                    eprintln!("error: unable to read `{}`: {}", path, error);
+                    success = false;
+                    continue;
+                },
+            };
+            if let Err(message) = self.run_file(path, &content) {
+                eprint!("{}", message);
+                success = false;
+            }
+        }
+        if success {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        }
+    }
+
+    fn run_file(&self, path: &str, content: &str) -> Result<(), String> {
+        let mut map = SourceMap::new();
+        let index = map.insert(Origin::from_named(path), content.into())
+            .try_into_inserted()
+            .expect("single source should not conflict");
+        let input = map.input(index);
+        let tree = Tree::parse(input.clone(), self.indent)
+            .map_err(|error| render_error(path, content, &error, Locator::new(input.clone())))?;
+        match self.command {
+            Command::Check => Ok(()),
+            Command::Format { check } => {
+                let formatted = format_source(content, &tree, self.indent)
+                    .map_err(|reason| format!("error: cannot format `{}`: {}\n", path, reason))?;
+                if formatted == content {
+                    Ok(())
+                } else if check {
+                    Err(format!("{}: not formatted\n", path))
+                } else {
+                    std::fs::write(path, formatted)
+                        .map_err(|error| format!("error: unable to write `{}`: {}\n", path, error))
+                }
+            },
+            Command::Dump { json } => {
+                let mut locator = Locator::new(input);
+                let lines = Lines::new(content);
+                if json {
+                    let nodes = tree.iter()
+                        .map(|node| dump_node_json(node, &mut locator, &lines))
+                        .collect::<Vec<_>>();
+                    let dump = serde_json::json!({ "file": path, "nodes": nodes });
+                    println!("{}", serde_json::to_string_pretty(&dump).unwrap());
+                } else {
+                    let mut dump = format!("{}\n", path);
+                    for node in tree.iter() {
+                        dump_node_tree(&mut dump, node, 1, &mut locator, &lines);
+                    }
+                    print!("{}", dump);
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+fn parse_indent(value: &str) -> Option<Indent> {
+    if value == "tabs" {
+        Some(Indent::tabs())
+    } else {
+        value.parse().ok().and_then(Indent::try_spaces)
+    }
+}
+
+/// Reformat the source, keeping everything following an `__END__` marker as it is.
+///
+/// Sources containing comments other than doc comments attached to nodes are rejected, since
+/// the tree does not preserve them.
+fn format_source(content: &str, tree: &Tree, indent: Indent) -> Result<String, &'static str> {
+    let mut end = None;
+    let mut start = 0;
+    for line in content.split_inclusive('\n') {
+        if line.trim() == "__END__" {
+            end = Some(start);
+            break;
+        }
+        start += line.len();
+    }
+    let (head, tail) = content.split_at(end.unwrap_or(content.len()));
+    let formatted = tree.to_source(indent);
+    // Doc comments not followed by a node are dropped as well.
+    if doc_lines(head).is_none() || doc_lines(head) != doc_lines(&formatted) {
+        return Err("comments would be removed");
+    }
+    Ok(formatted + tail)
+}
+
+
+/// Render the error with a snippet of its line, followed by snippets of its context.
+fn render_error(
+    path: &str,
+    content: &str,
+    error: &SourceError<ParseError>,
+    mut locator: Locator<'_>,
+) -> String {
+    let lines = Lines::new(content);
+    let mut position = |offset| lines.line_column(locator.byte_index(offset).unwrap_or(0));
+    let (line, column) = position(error.offset());
+    let context = error.context().iter().map(|&offset| position(offset)).collect::<Vec<_>>();
+    let width = std::iter::once(line).chain(context.iter().map(|&(line, _)| line))
+        .map(|line| (line + 1).to_string().len())
+        .max()
+        .unwrap_or(1);
+    let gutter = " ".repeat(width);
+    let mut rendered = String::new();
+    writeln!(rendered, "error: {}", error.error()).unwrap();
+    writeln!(rendered, "{}--> {}:{}:{}", gutter, path, line + 1, column + 1).unwrap();
+    render_snippet(&mut rendered, content, (line, column), width, "");
+    for (line, column) in context {
+        writeln!(rendered, "{}::: {}:{}:{}", gutter, path, line + 1, column + 1).unwrap();
+        render_snippet(&mut rendered, content, (line, column), width, " related");
+    }
+    rendered
+}
+
+fn render_snippet(
+    out: &mut String,
+    content: &str,
+    (line, column): (usize, usize),
+    width: usize,
+    label: &str,
+) {
+    let text = content.lines().nth(line).unwrap_or("");
+    let gutter = " ".repeat(width);
+    writeln!(out, "{} |", gutter).unwrap();
+    writeln!(out, "{:>width$} | {}", line + 1, text, width = width).unwrap();
+    writeln!(out, "{} | {}^{}", gutter, " ".repeat(column), label).unwrap();
+}
+
+fn dump_node_json(node: &Node, locator: &mut Locator<'_>, lines: &Lines<'_>) -> serde_json::Value {
+    let (line, column) = lines.offset_position(locator, node.location);
+    match &node.kind {
+        NodeKind::Directive(directive) => serde_json::json!({
+            "kind": "directive",
+            "line": line,
+            "column": column,
+            "signature": dump_items_json(&directive.signature, locator, lines),
+            "arguments": dump_items_json(&directive.arguments, locator, lines),
+            "children": directive.children.iter()
+                .map(|child| dump_node_json(child, locator, lines))
+                .collect::<Vec<_>>(),
+        }),
+        NodeKind::Statement(statement) => serde_json::json!({
+            "kind": "statement",
+            "line": line,
+            "column": column,
+            "signature": dump_items_json(&statement.signature, locator, lines),
+        }),
+    }
+}
+
+fn dump_items_json(items: &[Item], locator: &mut Locator<'_>, lines: &Lines<'_>) -> serde_json::Value {
+    items.iter().map(|item| dump_item_json(item, locator, lines)).collect()
+}
+
+fn dump_item_json(item: &Item, locator: &mut Locator<'_>, lines: &Lines<'_>) -> serde_json::Value {
+    let (line, column) = lines.offset_position(locator, item.location.start());
+    let (kind, value) = match &item.kind {
+        ItemKind::Word(word) => ("word", serde_json::json!(word.as_str())),
+        ItemKind::Int(value) => ("int", serde_json::json!(value)),
+        ItemKind::Float(value) => ("float", serde_json::json!(value)),
+        ItemKind::Bool(value) => ("bool", serde_json::json!(value)),
+        ItemKind::Null => ("null", serde_json::Value::Null),
+        ItemKind::Sigil { .. } => ("sigil", serde_json::json!(item.to_string())),
+        ItemKind::Parentheses(items) | ItemKind::Brackets(items) | ItemKind::Braces(items) => {
+            (item_kind_name(&item.kind), dump_items_json(items, locator, lines))
+        },
+        ItemKind::Pair { key, value, .. } => {
+            let pair = [dump_item_json(key, locator, lines), dump_item_json(value, locator, lines)];
+            ("pair", serde_json::json!(pair))
+        },
+    };
+    serde_json::json!({ "kind": kind, "line": line, "column": column, "value": value })
+}
+
+fn dump_node_tree(
+    out: &mut String,
+    node: &Node,
+    depth: usize,
+    locator: &mut Locator<'_>,
+    lines: &Lines<'_>,
+) {
+    let (line, column) = lines.offset_position(locator, node.location);
+    let indent = "  ".repeat(depth);
+    match &node.kind {
+        NodeKind::Directive(directive) => {
+            writeln!(out, "{}directive @{}:{}", indent, line, column).unwrap();
+            dump_items_tree(out, "signature", &directive.signature, depth + 1, locator, lines);
+            dump_items_tree(out, "arguments", &directive.arguments, depth + 1, locator, lines);
+            if !directive.children.is_empty() {
+                writeln!(out, "{}  children", indent).unwrap();
+                for child in &directive.children {
+                    dump_node_tree(out, child, depth + 2, locator, lines);
+                }
+            }
+        },
+        NodeKind::Statement(statement) => {
+            writeln!(out, "{}statement @{}:{}", indent, line, column).unwrap();
+            dump_items_tree(out, "signature", &statement.signature, depth + 1, locator, lines);
+        },
+    }
+}
+
+fn dump_items_tree(
+    out: &mut String,
+    label: &str,
+    items: &[Item],
+    depth: usize,
+    locator: &mut Locator<'_>,
+    lines: &Lines<'_>,
+) {
+    if items.is_empty() {
+        return;
+    }
+    writeln!(out, "{}{}", "  ".repeat(depth), label).unwrap();
+    for item in items {
+        dump_item_tree(out, item, depth + 1, locator, lines);
+    }
+}
+
+fn dump_item_tree(
+    out: &mut String,
+    item: &Item,
+    depth: usize,
+    locator: &mut Locator<'_>,
+    lines: &Lines<'_>,
+) {
+    let (line, column) = lines.offset_position(locator, item.location.start());
+    let indent = "  ".repeat(depth);
+    let kind = item_kind_name(&item.kind);
+    match &item.kind {
+        ItemKind::Parentheses(items) | ItemKind::Brackets(items) | ItemKind::Braces(items) => {
+            writeln!(out, "{}{} @{}:{}", indent, kind, line, column).unwrap();
+            for item in items {
+                dump_item_tree(out, item, depth + 1, locator, lines);
+            }
+        },
+        ItemKind::Pair { key, value, .. } => {
+            writeln!(out, "{}{} @{}:{}", indent, kind, line, column).unwrap();
+            dump_item_tree(out, key, depth + 1, locator, lines);
+            dump_item_tree(out, value, depth + 1, locator, lines);
+        },
+        _ => {
+            writeln!(out, "{}{} {} @{}:{}", indent, kind, item, line, column).unwrap();
+        },
+    }
+}
+
+
+/// Converts byte indices to line and column numbers.
+struct Lines<'a> {
+    content: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(content: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self { content, starts }
+    }
+
+    /// Zero-based line and character column of a byte index.
+    fn line_column(&self, index: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|&start| start <= index) - 1;
+        let column = self.content[self.starts[line]..index].chars().count();
+        (line, column)
+    }
+
+    /// One-based line and column of an offset, or zero if it cannot be located.
+    fn offset_position(&self, locator: &mut Locator<'_>, offset: src_ctx::Offset) -> (usize, usize) {
+        locator.byte_index(offset)
+            .map(|index| self.line_column(index))
+            .map_or((0, 0), |(line, column)| (line + 1, column + 1))
+    }
+}
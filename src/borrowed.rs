@@ -0,0 +1,224 @@
+//! A variant of [`Tree`](crate::Tree) borrowing its words from the source.
+//!
+//! Parsing into this representation avoids allocating each word. The tree can be converted
+//! into the owned representation with [`Tree::into_owned`].
+use src_ctx::{Input, Offset, Span};
+
+use crate::{Indent, ParseOptions, ParseResult};
+use crate::parse::parse_borrowed;
+
+
+/// A collection of [`Node`] roots borrowing from the source.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Tree<'src> {
+    pub roots: Vec<Node<'src>>,
+}
+
+impl<'src> Tree<'src> {
+    /// Try to parse a tree from the given input assuming the given [`Indent`].
+    pub fn parse(input: Input<'src>, indent: Indent) -> ParseResult<Self> {
+        parse_borrowed(input, ParseOptions::new(indent))
+    }
+
+    /// Try to parse a tree from the given input with the given [`ParseOptions`].
+    pub fn parse_with(input: Input<'src>, options: ParseOptions) -> ParseResult<Self> {
+        parse_borrowed(input, options)
+    }
+
+    /// Convert into an owned [`Tree`](crate::Tree).
+    pub fn into_owned(self) -> crate::Tree {
+        crate::Tree { roots: self.roots.into_iter().map(Node::into_owned).collect() }
+    }
+}
+
+impl<'src> std::ops::Deref for Tree<'src> {
+    type Target = Vec<Node<'src>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.roots
+    }
+}
+
+/// A parsed node in a [`Tree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node<'src> {
+    pub kind: NodeKind<'src>,
+    pub location: Offset,
+    pub docs: Vec<Doc<'src>>,
+    pub comment: Option<Comment<'src>>,
+}
+
+impl<'src> Node<'src> {
+    /// Get a slice of children independent of the [`NodeKind`].
+    pub fn children(&self) -> &[Self] {
+        match &self.kind {
+            NodeKind::Directive(directive) => &directive.children,
+            NodeKind::Statement(_) => &[],
+        }
+    }
+
+    /// Convert into an owned [`Node`](crate::Node).
+    pub fn into_owned(self) -> crate::Node {
+        let kind = match self.kind {
+            NodeKind::Directive(directive) => crate::NodeKind::Directive(crate::Directive {
+                signature: into_owned_items(directive.signature),
+                arguments: into_owned_items(directive.arguments),
+                children: directive.children.into_iter().map(Node::into_owned).collect(),
+            }),
+            NodeKind::Statement(statement) => crate::NodeKind::Statement(crate::Statement {
+                signature: into_owned_items(statement.signature),
+            }),
+        };
+        let docs = self.docs.into_iter()
+            .map(|doc| crate::Doc { text: doc.text.into(), location: doc.location })
+            .collect();
+        let comment = self.comment.map(|comment| {
+            crate::Comment { text: comment.text.into(), location: comment.location }
+        });
+        crate::Node { kind, location: self.location, docs, comment }
+    }
+}
+
+impl<'src> std::ops::Deref for Node<'src> {
+    type Target = NodeKind<'src>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.kind
+    }
+}
+
+/// A doc comment preceding a [`Node`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Doc<'src> {
+    pub text: &'src str,
+    pub location: Span,
+}
+
+/// A comment at the end of a [`Node`]'s line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment<'src> {
+    pub text: &'src str,
+    pub location: Span,
+}
+
+/// Data for a [`NodeKind::Directive`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive<'src> {
+    pub signature: Vec<Item<'src>>,
+    pub arguments: Vec<Item<'src>>,
+    pub children: Vec<Node<'src>>,
+}
+
+/// Data for a [`NodeKind::Statement`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement<'src> {
+    pub signature: Vec<Item<'src>>,
+}
+
+/// The different kinds of [`Node`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind<'src> {
+    Directive(Directive<'src>),
+    Statement(Statement<'src>),
+}
+
+impl<'src> NodeKind<'src> {
+    fn_enum_is_variant!(is_directive, Directive);
+    fn_enum_is_variant!(is_statement, Statement);
+
+    fn_enum_try_into_variant!(try_into_directive, Directive, Directive<'src>);
+    fn_enum_try_into_variant!(try_into_statement, Statement, Statement<'src>);
+
+    fn_enum_variant_access!(directive -> &Directive<'src>, Self::Directive(directive) => directive);
+    fn_enum_variant_access!(statement -> &Statement<'src>, Self::Statement(statement) => statement);
+}
+
+/// An item of [`ItemKind`] found in a [`Statement`] or [`Directive`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item<'src> {
+    pub kind: ItemKind<'src>,
+    pub location: Span,
+}
+
+impl Item<'_> {
+    /// Convert into an owned [`Item`](crate::Item).
+    pub fn into_owned(self) -> crate::Item {
+        let kind = match self.kind {
+            ItemKind::Word(word) => crate::ItemKind::Word(word.into()),
+            ItemKind::Int(value) => crate::ItemKind::Int(value),
+            ItemKind::Float(value) => crate::ItemKind::Float(value),
+            ItemKind::Bool(value) => crate::ItemKind::Bool(value),
+            ItemKind::Null => crate::ItemKind::Null,
+            ItemKind::Sigil { sigil, name } => crate::ItemKind::Sigil { sigil, name: name.into() },
+            ItemKind::Parentheses(items) => crate::ItemKind::Parentheses(into_owned_items(items)),
+            ItemKind::Brackets(items) => crate::ItemKind::Brackets(into_owned_items(items)),
+            ItemKind::Braces(items) => crate::ItemKind::Braces(into_owned_items(items)),
+            ItemKind::Pair { key, separator, value } => crate::ItemKind::Pair {
+                key: Box::new(key.into_owned()),
+                separator,
+                value: Box::new(value.into_owned()),
+            },
+        };
+        crate::Item { kind, location: self.location }
+    }
+}
+
+impl<'src> std::ops::Deref for Item<'src> {
+    type Target = ItemKind<'src>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.kind
+    }
+}
+
+/// The different kinds of [`Item`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind<'src> {
+    Word(&'src str),
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    Null,
+    Sigil { sigil: char, name: &'src str },
+    Parentheses(Vec<Item<'src>>),
+    Brackets(Vec<Item<'src>>),
+    Braces(Vec<Item<'src>>),
+    Pair { key: Box<Item<'src>>, separator: char, value: Box<Item<'src>> },
+}
+
+impl<'src> ItemKind<'src> {
+    fn_enum_is_variant!(is_word, Word);
+    fn_enum_is_variant!(is_int, Int);
+    fn_enum_is_variant!(is_float, Float);
+    fn_enum_is_variant!(is_bool, Bool);
+    fn_enum_is_variant!(is_null, Null);
+    fn_enum_is_variant!(is_sigil, Sigil);
+    fn_enum_is_variant!(is_parenthesized, Parentheses);
+    fn_enum_is_variant!(is_bracketed, Brackets);
+    fn_enum_is_variant!(is_braced, Braces);
+    fn_enum_is_variant!(is_pair, Pair);
+
+    fn_enum_try_into_variant!(try_into_word, Word, &'src str);
+    fn_enum_try_into_variant!(try_into_int, Int, i32);
+    fn_enum_try_into_variant!(try_into_float, Float, f32);
+    fn_enum_try_into_variant!(try_into_bool, Bool, bool);
+    fn_enum_try_into_variant!(try_into_parenthesized, Parentheses, Vec<Item<'src>>);
+    fn_enum_try_into_variant!(try_into_bracketed, Brackets, Vec<Item<'src>>);
+    fn_enum_try_into_variant!(try_into_braced, Braces, Vec<Item<'src>>);
+
+    fn_enum_variant_access!(word -> &'src str, Self::Word(word) => *word);
+    fn_enum_variant_access!(int -> i32, Self::Int(value) => *value);
+    fn_enum_variant_access!(float -> f32, Self::Float(value) => *value);
+    fn_enum_variant_access!(bool -> bool, Self::Bool(value) => *value);
+    fn_enum_variant_access!(parenthesized -> &[Item<'src>], Self::Parentheses(items) => items);
+    fn_enum_variant_access!(bracketed -> &[Item<'src>], Self::Brackets(items) => items);
+    fn_enum_variant_access!(braced -> &[Item<'src>], Self::Braces(items) => items);
+    fn_enum_variant_access!(
+        pair -> (&Item<'src>, &Item<'src>),
+        Self::Pair { key, value, .. } => (key, value)
+    );
+}
+
+fn into_owned_items(items: Vec<Item<'_>>) -> Vec<crate::Item> {
+    items.into_iter().map(Item::into_owned).collect()
+}
@@ -0,0 +1,614 @@
+//! Conversion between [`Tree`] values and JSON, YAML or TOML documents.
+//!
+//! # Mapping
+//!
+//! A tree is an array of nodes, and every node and group item is an object tagged by its key:
+//!
+//! | treelang                        | document |
+//! |---------------------------------|----------|
+//! | [`Tree`]                        | `[<node>...]` |
+//! | [`Statement`](crate::Statement) | `{"statement": [<item>...]}` |
+//! | [`Directive`](crate::Directive) | `{"directive": [<item>...], "arguments": [<item>...], "children": [<node>...]}` |
+//! | [`ItemKind::Word`]              | `"word"` |
+//! | [`ItemKind::Int`]               | `23` |
+//! | [`ItemKind::Float`]             | `23.5` |
+//! | [`ItemKind::Bool`]              | `true` or `false` |
+//! | [`ItemKind::Null`]              | `null` |
+//! | [`ItemKind::Sigil`]             | `{"sigil": "$", "name": "name"}` |
+//! | [`ItemKind::Parentheses`]       | `{"parentheses": [<item>...]}` |
+//! | [`ItemKind::Brackets`]          | `{"brackets": [<item>...]}` |
+//! | [`ItemKind::Braces`]            | `{"braces": [<item>...]}` |
+//! | [`ItemKind::Pair`]              | `{"pair": [<key>, <item>], "separator": "="}` |
+//!
+//! Nodes with doc comments have a `docs` array with the text of each, and nodes with a trailing
+//! comment have a `comment` string. The `arguments` and `children` of directives, `docs` and
+//! `comment` are optional when converting from a document.
+//!
+//! Since every tree node carries a source location, documents are first converted to source
+//! text, which is added to a [`SourceMap`] and parsed with the given [`ParseOptions`]. The
+//! locations of the resulting tree refer to that source. Booleans and null are written as words
+//! of [`ParseOptions::literals`], sigils need [`ParseOptions::sigils`], pairs need
+//! [`ParseOptions::pair_separator`] and trailing comments need
+//! [`ParseOptions::trailing_comments`]. Anything parsed back differently, such as a string that
+//! equals a literal word or starts with a sigil, is reported as a [`Loss`].
+//!
+//! Values that cannot be represented exactly are reported as [`Loss`]es, values that cannot
+//! be represented at all are reported as a [`ConvertError`].
+//!
+//! YAML documents use the same mapping. TOML documents require a table at the top level, so
+//! the nodes are stored as an array in its `nodes` key.
+use serde_json::{Map, Number, Value};
+use smol_str::SmolStr;
+use src_ctx::{SourceMap, Origin};
+
+use crate::{Tree, Node, NodeKind, Item, ItemKind, ParseOptions, ParseError};
+use crate::parse::{is_valid_word, is_valid_pair_key, word_kind, literal_word};
+
+
+const STATEMENT: &str = "statement";
+const DIRECTIVE: &str = "directive";
+const ARGUMENTS: &str = "arguments";
+const CHILDREN: &str = "children";
+const PARENTHESES: &str = "parentheses";
+const BRACKETS: &str = "brackets";
+const BRACES: &str = "braces";
+const PAIR: &str = "pair";
+const SEPARATOR: &str = "separator";
+const SIGIL: &str = "sigil";
+const NAME: &str = "name";
+const DOCS: &str = "docs";
+const COMMENT: &str = "comment";
+
+#[cfg(feature = "toml")]
+const TOML_NODES: &str = "nodes";
+
+/// A converted value together with the [`Loss`]es encountered during conversion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Converted<T> {
+    pub value: T,
+    pub losses: Vec<Loss>,
+}
+
+impl<T> Converted<T> {
+    /// Returns `true` if the value was converted exactly.
+    pub fn is_lossless(&self) -> bool {
+        self.losses.is_empty()
+    }
+}
+
+/// A value that was not converted exactly.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{kind} at `{path}`")]
+pub struct Loss {
+    /// JSON pointer to the affected value in the document.
+    pub path: String,
+    pub kind: LossKind,
+}
+
+/// The different kinds of [`Loss`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LossKind {
+    #[error("Non-finite float `{value}` converted to null")]
+    NonFiniteFloat { value: f32 },
+    #[error("Float `{value}` rounded to `{rounded}`")]
+    FloatPrecision { value: f64, rounded: f32 },
+    #[error("Unknown key `{key}` ignored")]
+    UnknownKey { key: SmolStr },
+    #[error("Value written as `{word}` is parsed as a different item")]
+    Reinterpreted { word: SmolStr },
+    #[error("Trailing comment dropped")]
+    CommentDropped,
+}
+
+/// A value that cannot be converted.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{kind} at `{path}`")]
+pub struct ConvertError {
+    /// JSON pointer to the affected value in the document.
+    pub path: String,
+    pub kind: ConvertErrorKind,
+}
+
+/// The different kinds of [`ConvertError`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConvertErrorKind {
+    #[error("Invalid document syntax: {message}")]
+    Syntax { message: String },
+    #[error("Expected an array")]
+    ExpectedArray,
+    #[error("Expected a `statement` or `directive` object")]
+    ExpectedNode,
+    #[error("Expected a word, number, boolean, null, group, sigil or pair object")]
+    ExpectedItem,
+    #[error("Node has both `statement` and `directive` keys")]
+    AmbiguousNode,
+    #[error("Group has more than one kind of group key")]
+    AmbiguousGroup,
+    #[error("Empty node signature")]
+    EmptySignature,
+    #[error("Expected a `pair` array with a key and a value, and a single `separator` character")]
+    InvalidPair,
+    #[error("Expected a single `sigil` character and a `name` string")]
+    InvalidSigil,
+    #[error("String `{value}` is not a valid word")]
+    InvalidWord { value: String },
+    #[error("Expected a single line comment string")]
+    InvalidComment,
+    #[error("Integer `{value}` is out of range")]
+    IntOutOfRange { value: Number },
+    #[error("Float `{value}` is out of range")]
+    FloatOutOfRange { value: f64 },
+    #[error("Origin is already used by a different source")]
+    OriginConflict,
+    #[error("Converted source cannot be parsed: {error}")]
+    Parse { error: ParseError },
+    #[error("Value cannot be represented: {message}")]
+    Unsupported { message: String },
+}
+
+/// Convert a tree into a JSON value.
+pub fn to_json(tree: &Tree) -> Converted<Value> {
+    let mut losses = Vec::new();
+    let value = tree.iter().enumerate()
+        .map(|(index, node)| node_to_json(node, &format!("/{}", index), &mut losses))
+        .collect();
+    Converted { value, losses }
+}
+
+/// Convert a JSON value into a tree parsed with `options` from source added to `map` as
+/// `origin`.
+pub fn from_json(
+    value: &Value,
+    options: ParseOptions,
+    map: &mut SourceMap,
+    origin: Origin,
+) -> Result<Converted<Tree>, ConvertError> {
+    let mut writer = SourceWriter::new(options);
+    writer.nodes(value, "", 0)?;
+    writer.parse(map, origin)
+}
+
+/// Convert a tree into a YAML document.
+#[cfg(feature = "yaml")]
+pub fn to_yaml(tree: &Tree) -> Converted<String> {
+    let Converted { value, losses } = to_json(tree);
+    let value = serde_yaml::to_string(&value).expect("JSON values are valid YAML");
+    Converted { value, losses }
+}
+
+/// Convert a YAML document into a tree parsed with `options` from source added to `map` as
+/// `origin`.
+#[cfg(feature = "yaml")]
+pub fn from_yaml(
+    document: &str,
+    options: ParseOptions,
+    map: &mut SourceMap,
+    origin: Origin,
+) -> Result<Converted<Tree>, ConvertError> {
+    let value = serde_yaml::from_str(document)
+        .map_err(|error| syntax_error(error.to_string()))?;
+    from_json(&value, options, map, origin)
+}
+
+/// Convert a tree into a TOML document.
+#[cfg(feature = "toml")]
+pub fn to_toml(tree: &Tree) -> Result<Converted<String>, ConvertError> {
+    let Converted { value, losses } = to_json(tree);
+    let mut document = Map::new();
+    document.insert(TOML_NODES.into(), value);
+    let value = toml::to_string(&document).map_err(|error| ConvertError {
+        path: String::new(),
+        kind: ConvertErrorKind::Unsupported { message: error.to_string() },
+    })?;
+    Ok(Converted { value, losses })
+}
+
+/// Convert a TOML document into a tree parsed with `options` from source added to `map` as
+/// `origin`.
+#[cfg(feature = "toml")]
+pub fn from_toml(
+    document: &str,
+    options: ParseOptions,
+    map: &mut SourceMap,
+    origin: Origin,
+) -> Result<Converted<Tree>, ConvertError> {
+    let mut value: Map<String, Value> = toml::from_str(document)
+        .map_err(|error| syntax_error(error.to_string()))?;
+    let nodes = value.remove(TOML_NODES).unwrap_or(Value::Array(Vec::new()));
+    let mut writer = SourceWriter::new(options);
+    for key in value.keys() {
+        writer.losses.push(Loss {
+            path: format!("/{}", escape_pointer(key)),
+            kind: LossKind::UnknownKey { key: key.as_str().into() },
+        });
+    }
+    writer.nodes(&nodes, &format!("/{}", TOML_NODES), 0)?;
+    writer.parse(map, origin)
+}
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+fn syntax_error(message: String) -> ConvertError {
+    ConvertError { path: String::new(), kind: ConvertErrorKind::Syntax { message } }
+}
+
+fn node_to_json(node: &Node, path: &str, losses: &mut Vec<Loss>) -> Value {
+    let mut object = Map::new();
+    match &node.kind {
+        NodeKind::Statement(statement) => {
+            let path = format!("{}/{}", path, STATEMENT);
+            object.insert(STATEMENT.into(), items_to_json(&statement.signature, &path, losses));
+        },
+        NodeKind::Directive(directive) => {
+            let signature_path = format!("{}/{}", path, DIRECTIVE);
+            let signature = items_to_json(&directive.signature, &signature_path, losses);
+            object.insert(DIRECTIVE.into(), signature);
+
+            let arguments_path = format!("{}/{}", path, ARGUMENTS);
+            let arguments = items_to_json(&directive.arguments, &arguments_path, losses);
+            object.insert(ARGUMENTS.into(), arguments);
+
+            let children = directive.children.iter().enumerate()
+                .map(|(index, child)| {
+                    node_to_json(child, &format!("{}/{}/{}", path, CHILDREN, index), losses)
+                })
+                .collect();
+            object.insert(CHILDREN.into(), children);
+        },
+    }
+    if !node.docs.is_empty() {
+        let docs = node.docs.iter().map(|doc| Value::String(doc.text.as_str().into())).collect();
+        object.insert(DOCS.into(), docs);
+    }
+    if let Some(comment) = &node.comment {
+        object.insert(COMMENT.into(), Value::String(comment.text.as_str().into()));
+    }
+    Value::Object(object)
+}
+
+fn items_to_json(items: &[Item], path: &str, losses: &mut Vec<Loss>) -> Value {
+    items.iter().enumerate()
+        .map(|(index, item)| item_to_json(item, &format!("{}/{}", path, index), losses))
+        .collect()
+}
+
+fn item_to_json(item: &Item, path: &str, losses: &mut Vec<Loss>) -> Value {
+    let group = |key: &str, items: &[Item], losses: &mut Vec<Loss>| {
+        let mut object = Map::new();
+        object.insert(key.into(), items_to_json(items, &format!("{}/{}", path, key), losses));
+        Value::Object(object)
+    };
+    match &item.kind {
+        ItemKind::Word(word) => Value::String(word.as_str().into()),
+        ItemKind::Int(value) => Value::Number((*value).into()),
+        ItemKind::Bool(value) => Value::Bool(*value),
+        ItemKind::Null => Value::Null,
+        ItemKind::Sigil { sigil, name } => {
+            let mut object = Map::new();
+            object.insert(SIGIL.into(), Value::String(sigil.to_string()));
+            object.insert(NAME.into(), Value::String(name.as_str().into()));
+            Value::Object(object)
+        },
+        ItemKind::Float(value) => {
+            // Going through the shortest representation keeps `0.1` from becoming
+            // `0.10000000149011612`.
+            let number = value.to_string().parse().ok().and_then(Number::from_f64);
+            if let Some(number) = number {
+                Value::Number(number)
+            } else {
+                losses.push(Loss {
+                    path: path.into(),
+                    kind: LossKind::NonFiniteFloat { value: *value },
+                });
+                Value::Null
+            }
+        },
+        ItemKind::Parentheses(items) => group(PARENTHESES, items, losses),
+        ItemKind::Brackets(items) => group(BRACKETS, items, losses),
+        ItemKind::Braces(items) => group(BRACES, items, losses),
+        ItemKind::Pair { key, separator, value } => {
+            let pair = vec![
+                item_to_json(key, &format!("{}/{}/0", path, PAIR), losses),
+                item_to_json(value, &format!("{}/{}/1", path, PAIR), losses),
+            ];
+            let mut object = Map::new();
+            object.insert(PAIR.into(), Value::Array(pair));
+            object.insert(SEPARATOR.into(), Value::String(separator.to_string()));
+            Value::Object(object)
+        },
+    }
+}
+
+struct SourceWriter {
+    source: String,
+    options: ParseOptions,
+    losses: Vec<Loss>,
+}
+
+impl SourceWriter {
+    fn new(options: ParseOptions) -> Self {
+        Self { source: String::new(), options, losses: Vec::new() }
+    }
+
+    fn parse(self, map: &mut SourceMap, origin: Origin) -> Result<Converted<Tree>, ConvertError> {
+        let index = map.insert(origin, self.source.as_str().into())
+            .try_into_inserted()
+            .map_err(|_| error("", ConvertErrorKind::OriginConflict))?;
+        let tree = Tree::parse_with(map.input(index), self.options)
+            .map_err(|source_error| {
+                error("", ConvertErrorKind::Parse { error: source_error.error().clone() })
+            })?;
+        Ok(Converted { value: tree, losses: self.losses })
+    }
+
+    fn nodes(&mut self, value: &Value, path: &str, depth: usize) -> Result<(), ConvertError> {
+        for (index, node) in expect_array(value, path)?.iter().enumerate() {
+            self.node(node, &format!("{}/{}", path, index), depth)?;
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, value: &Value, path: &str, depth: usize) -> Result<(), ConvertError> {
+        let object = value.as_object().ok_or_else(|| error(path, ConvertErrorKind::ExpectedNode))?;
+        let indent = self.options.indent();
+        let known: &[&str] = match (object.get(STATEMENT), object.get(DIRECTIVE)) {
+            (Some(_), Some(_)) => return Err(error(path, ConvertErrorKind::AmbiguousNode)),
+            (None, None) => return Err(error(path, ConvertErrorKind::ExpectedNode)),
+            (Some(signature), None) => {
+                self.docs(object, path, depth)?;
+                indent.write_prefix(&mut self.source, depth);
+                self.signature(signature, &format!("{}/{}", path, STATEMENT))?;
+                self.comment(object, path)?;
+                self.source.push('\n');
+                &[STATEMENT, DOCS, COMMENT]
+            },
+            (None, Some(signature)) => {
+                self.docs(object, path, depth)?;
+                indent.write_prefix(&mut self.source, depth);
+                self.signature(signature, &format!("{}/{}", path, DIRECTIVE))?;
+                self.source.push(':');
+                if let Some(arguments) = object.get(ARGUMENTS) {
+                    let path = format!("{}/{}", path, ARGUMENTS);
+                    for (index, item) in expect_array(arguments, &path)?.iter().enumerate() {
+                        self.source.push(' ');
+                        self.item(item, &format!("{}/{}", path, index))?;
+                    }
+                }
+                self.comment(object, path)?;
+                self.source.push('\n');
+                if let Some(children) = object.get(CHILDREN) {
+                    self.nodes(children, &format!("{}/{}", path, CHILDREN), depth + 1)?;
+                }
+                &[DIRECTIVE, ARGUMENTS, CHILDREN, DOCS, COMMENT]
+            },
+        };
+        self.unknown_keys(object, known, path);
+        Ok(())
+    }
+
+    fn docs(
+        &mut self,
+        object: &Map<String, Value>,
+        path: &str,
+        depth: usize,
+    ) -> Result<(), ConvertError> {
+        let Some(docs) = object.get(DOCS) else {
+            return Ok(());
+        };
+        let path = format!("{}/{}", path, DOCS);
+        for (index, doc) in expect_array(docs, &path)?.iter().enumerate() {
+            let text = comment_text(doc, &format!("{}/{}", path, index))?;
+            self.options.indent().write_prefix(&mut self.source, depth);
+            self.source.push_str(";;");
+            if !text.is_empty() {
+                self.source.push(' ');
+                self.source.push_str(text);
+            }
+            self.source.push('\n');
+        }
+        Ok(())
+    }
+
+    fn comment(&mut self, object: &Map<String, Value>, path: &str) -> Result<(), ConvertError> {
+        let Some(comment) = object.get(COMMENT) else {
+            return Ok(());
+        };
+        let path = format!("{}/{}", path, COMMENT);
+        let text = comment_text(comment, &path)?;
+        if !self.options.records_comments() {
+            self.losses.push(Loss { path, kind: LossKind::CommentDropped });
+            return Ok(());
+        }
+        self.source.push_str(" ;");
+        if !text.is_empty() {
+            self.source.push(' ');
+            self.source.push_str(text);
+        }
+        Ok(())
+    }
+
+    fn signature(&mut self, value: &Value, path: &str) -> Result<(), ConvertError> {
+        let items = expect_array(value, path)?;
+        if items.is_empty() {
+            return Err(error(path, ConvertErrorKind::EmptySignature));
+        }
+        self.items(items, path)
+    }
+
+    fn items(&mut self, items: &[Value], path: &str) -> Result<(), ConvertError> {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.source.push(' ');
+            }
+            self.item(item, &format!("{}/{}", path, index))?;
+        }
+        Ok(())
+    }
+
+    fn item(&mut self, value: &Value, path: &str) -> Result<(), ConvertError> {
+        match value {
+            Value::String(word) => {
+                if !is_valid_word(word) {
+                    let kind = ConvertErrorKind::InvalidWord { value: word.clone() };
+                    return Err(error(path, kind));
+                }
+                self.word(word, ItemKind::Word(word.as_str().into()), path);
+            },
+            Value::Number(number) => {
+                if let Some(value) = number.as_f64().filter(|_| number.is_f64()) {
+                    let rounded = value as f32;
+                    if !rounded.is_finite() {
+                        return Err(error(path, ConvertErrorKind::FloatOutOfRange { value }));
+                    }
+                    if rounded.to_string().parse::<f64>().ok() != Some(value) {
+                        self.losses.push(Loss {
+                            path: path.into(),
+                            kind: LossKind::FloatPrecision { value, rounded },
+                        });
+                    }
+                    self.source.push_str(&ItemKind::Float(rounded).to_string());
+                } else {
+                    let value = number.as_i64()
+                        .and_then(|value| i32::try_from(value).ok())
+                        .ok_or_else(|| {
+                            error(path, ConvertErrorKind::IntOutOfRange { value: number.clone() })
+                        })?;
+                    self.source.push_str(&value.to_string());
+                }
+            },
+            Value::Object(object) if object.contains_key(PAIR) => self.pair(object, path)?,
+            Value::Object(object) if object.contains_key(SIGIL) => self.sigil(object, path)?,
+            Value::Object(object) => {
+                let groups = [
+                    (PARENTHESES, '(', ')'),
+                    (BRACKETS, '[', ']'),
+                    (BRACES, '{', '}'),
+                ];
+                let mut found = groups.iter()
+                    .filter_map(|&(key, open, close)| Some((key, open, close, object.get(key)?)));
+                let (key, open, close, items) = found.next()
+                    .ok_or_else(|| error(path, ConvertErrorKind::ExpectedItem))?;
+                if found.next().is_some() {
+                    return Err(error(path, ConvertErrorKind::AmbiguousGroup));
+                }
+                let items_path = format!("{}/{}", path, key);
+                self.source.push(open);
+                self.items(expect_array(items, &items_path)?, &items_path)?;
+                self.source.push(close);
+                self.unknown_keys(object, &[key], path);
+            },
+            Value::Bool(value) => {
+                let word = literal_word(Some(*value), &self.options)
+                    .unwrap_or(if *value { "true" } else { "false" });
+                self.word(word, ItemKind::Bool(*value), path);
+            },
+            Value::Null => {
+                let word = literal_word(None, &self.options).unwrap_or("null");
+                self.word(word, ItemKind::Null, path);
+            },
+            Value::Array(_) => {
+                return Err(error(path, ConvertErrorKind::ExpectedItem));
+            },
+        }
+        Ok(())
+    }
+
+    fn pair(&mut self, object: &Map<String, Value>, path: &str) -> Result<(), ConvertError> {
+        let pair_path = format!("{}/{}", path, PAIR);
+        let (key, value) = match expect_array(&object[PAIR], &pair_path)? {
+            [Value::String(key), value] => (key, value),
+            _ => return Err(error(&pair_path, ConvertErrorKind::InvalidPair)),
+        };
+        let separator = single_char(object.get(SEPARATOR))
+            .ok_or_else(|| error(path, ConvertErrorKind::InvalidPair))?;
+        if !is_valid_pair_key(key, separator) {
+            let kind = ConvertErrorKind::InvalidWord { value: key.clone() };
+            return Err(error(&format!("{}/0", pair_path), kind));
+        }
+        // The value of a pair ends at the next separator, so it cannot contain another one.
+        let value_path = format!("{}/1", pair_path);
+        match value {
+            Value::String(word) if word.contains(separator) => {
+                let kind = ConvertErrorKind::InvalidWord { value: word.clone() };
+                return Err(error(&value_path, kind));
+            },
+            Value::Object(object) if object.contains_key(PAIR) => {
+                return Err(error(&value_path, ConvertErrorKind::InvalidPair));
+            },
+            _ => (),
+        }
+        if self.options.separator() != Some(separator) {
+            self.losses.push(Loss {
+                path: path.into(),
+                kind: LossKind::Reinterpreted { word: format!("{}{}", key, separator).into() },
+            });
+        }
+        self.source.push_str(key);
+        self.source.push(separator);
+        self.item(value, &value_path)?;
+        self.unknown_keys(object, &[PAIR, SEPARATOR], path);
+        Ok(())
+    }
+
+    fn sigil(&mut self, object: &Map<String, Value>, path: &str) -> Result<(), ConvertError> {
+        let sigil = single_char(object.get(SIGIL));
+        let name = object.get(NAME).and_then(Value::as_str);
+        let (Some(sigil), Some(name)) = (sigil, name) else {
+            return Err(error(path, ConvertErrorKind::InvalidSigil));
+        };
+        let word = format!("{}{}", sigil, name);
+        if name.is_empty() || !is_valid_word(&word) {
+            return Err(error(path, ConvertErrorKind::InvalidWord { value: word }));
+        }
+        self.word(&word, ItemKind::Sigil { sigil, name: name.into() }, path);
+        self.unknown_keys(object, &[SIGIL, NAME], path);
+        Ok(())
+    }
+
+    /// Write a valid word, recording a [`Loss`] if it is not parsed back as `kind`.
+    fn word(&mut self, word: &str, kind: ItemKind, path: &str) {
+        if word_kind(word, &self.options) != Some(kind) {
+            self.losses.push(Loss {
+                path: path.into(),
+                kind: LossKind::Reinterpreted { word: word.into() },
+            });
+        }
+        self.source.push_str(word);
+    }
+
+    fn unknown_keys(&mut self, object: &Map<String, Value>, known: &[&str], path: &str) {
+        for key in object.keys().filter(|key| !known.contains(&key.as_str())) {
+            self.losses.push(Loss {
+                path: format!("{}/{}", path, escape_pointer(key)),
+                kind: LossKind::UnknownKey { key: key.as_str().into() },
+            });
+        }
+    }
+}
+
+fn expect_array<'v>(value: &'v Value, path: &str) -> Result<&'v [Value], ConvertError> {
+    value.as_array()
+        .map(|values| values.as_slice())
+        .ok_or_else(|| error(path, ConvertErrorKind::ExpectedArray))
+}
+
+fn single_char(value: Option<&Value>) -> Option<char> {
+    let mut chars = value?.as_str()?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(char), None) => Some(char),
+        _ => None,
+    }
+}
+
+fn comment_text<'v>(value: &'v Value, path: &str) -> Result<&'v str, ConvertError> {
+    value.as_str()
+        .filter(|text| !text.contains(['\n', '\r']))
+        .ok_or_else(|| error(path, ConvertErrorKind::InvalidComment))
+}
+
+fn error(path: &str, kind: ConvertErrorKind) -> ConvertError {
+    ConvertError { path: path.into(), kind }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
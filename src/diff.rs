@@ -0,0 +1,446 @@
+//! Structural differences between two [`Tree`] values.
+//!
+//! Locations and comments are ignored. Sibling nodes are matched by their signature, and nodes
+//! with a different signature but the same first item are reported as modified. Items are
+//! compared by kind and value, so a literal `true` differs from the word `true`, and floats
+//! are compared bitwise.
+use std::fmt;
+
+use crate::{Tree, Node, NodeKind, Item, ItemKind};
+
+
+/// A step in a [`Path`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// A root or child node.
+    Node(usize),
+    /// An item in the signature of a node.
+    Signature(usize),
+    /// An item in the arguments of a directive.
+    Argument(usize),
+    /// An item inside of a group.
+    Group(usize),
+}
+
+/// A path to a node or item, displayed like `/0/2/signature/1`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Path(pub Vec<Step>);
+
+impl Path {
+    fn join(&self, step: Step) -> Self {
+        let mut steps = self.0.clone();
+        steps.push(step);
+        Self(steps)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.0 {
+            match step {
+                Step::Node(index) | Step::Group(index) => write!(f, "/{}", index)?,
+                Step::Signature(index) => write!(f, "/signature/{}", index)?,
+                Step::Argument(index) => write!(f, "/arguments/{}", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A single change in a [`Diff`].
+///
+/// Paths of removed nodes and items, and the `from` paths, refer to the old tree. All other
+/// paths refer to the new tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    InsertNode { path: Path, node: Node },
+    RemoveNode { path: Path, node: Node },
+    MoveNode { from: Path, to: Path },
+    InsertItem { path: Path, item: Item },
+    RemoveItem { path: Path, item: Item },
+    MoveItem { from: Path, to: Path },
+    ModifyItem { from: Path, to: Path, old: Item, new: Item },
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InsertNode { path, node } => write!(f, "+ {} {}", path, Header(node)),
+            Self::RemoveNode { path, node } => write!(f, "- {} {}", path, Header(node)),
+            Self::MoveNode { from, to } => write!(f, "> {} -> {}", from, to),
+            Self::InsertItem { path, item } => write!(f, "+ {} `{}`", path, item),
+            Self::RemoveItem { path, item } => write!(f, "- {} `{}`", path, item),
+            Self::MoveItem { from, to } => write!(f, "> {} -> {}", from, to),
+            Self::ModifyItem { to, old, new, .. } => write!(f, "~ {} `{}` -> `{}`", to, old, new),
+        }
+    }
+}
+
+/// The edit script turning one [`Tree`] into another.
+///
+/// Displays as one line per [`Edit`], prefixed with `+` for insertions, `-` for removals, `>`
+/// for moves and `~` for modifications.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diff {
+    pub edits: Vec<Edit>,
+}
+
+impl Diff {
+    /// Compute the differences from `old` to `new`.
+    pub fn new(old: &Tree, new: &Tree) -> Self {
+        let mut diff = Self::default();
+        diff.nodes(old, new, &Path::default(), &Path::default());
+        diff
+    }
+
+    /// Returns `true` if the trees are structurally equal.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    fn nodes(&mut self, old: &[Node], new: &[Node], old_path: &Path, new_path: &Path) {
+        let pairs = align(old, new, node_key, |old, new| {
+            old.is_directive() == new.is_directive()
+                && signature(old).first().map(item_key) == signature(new).first().map(item_key)
+        });
+        for pair in pairs {
+            match pair {
+                Pair::Removed(index) => self.edits.push(Edit::RemoveNode {
+                    path: old_path.join(Step::Node(index)),
+                    node: old[index].clone(),
+                }),
+                Pair::Inserted(index) => self.edits.push(Edit::InsertNode {
+                    path: new_path.join(Step::Node(index)),
+                    node: new[index].clone(),
+                }),
+                Pair::Same(old_index, new_index)
+                | Pair::Moved(old_index, new_index)
+                | Pair::Changed(old_index, new_index) => {
+                    let from = old_path.join(Step::Node(old_index));
+                    let to = new_path.join(Step::Node(new_index));
+                    if let Pair::Moved(..) = pair {
+                        self.edits.push(Edit::MoveNode { from: from.clone(), to: to.clone() });
+                    }
+                    self.node(&old[old_index], &new[new_index], &from, &to);
+                },
+            }
+        }
+    }
+
+    fn node(&mut self, old: &Node, new: &Node, old_path: &Path, new_path: &Path) {
+        self.items(signature(old), signature(new), old_path, new_path, Step::Signature);
+        if let (NodeKind::Directive(old), NodeKind::Directive(new)) = (&old.kind, &new.kind) {
+            self.items(&old.arguments, &new.arguments, old_path, new_path, Step::Argument);
+            self.nodes(&old.children, &new.children, old_path, new_path);
+        }
+    }
+
+    fn items(
+        &mut self,
+        old: &[Item],
+        new: &[Item],
+        old_path: &Path,
+        new_path: &Path,
+        step: fn(usize) -> Step,
+    ) {
+        for pair in align(old, new, item_key, |_, _| true) {
+            match pair {
+                Pair::Same(..) => {},
+                Pair::Removed(index) => self.edits.push(Edit::RemoveItem {
+                    path: old_path.join(step(index)),
+                    item: old[index].clone(),
+                }),
+                Pair::Inserted(index) => self.edits.push(Edit::InsertItem {
+                    path: new_path.join(step(index)),
+                    item: new[index].clone(),
+                }),
+                Pair::Moved(old_index, new_index) => self.edits.push(Edit::MoveItem {
+                    from: old_path.join(step(old_index)),
+                    to: new_path.join(step(new_index)),
+                }),
+                Pair::Changed(old_index, new_index) => {
+                    let (old, new) = (&old[old_index], &new[new_index]);
+                    let from = old_path.join(step(old_index));
+                    let to = new_path.join(step(new_index));
+                    match (group_items(old), group_items(new)) {
+                        (Some((old_kind, old_items)), Some((new_kind, new_items)))
+                            if old_kind == new_kind =>
+                        {
+                            self.items(old_items, new_items, &from, &to, Step::Group);
+                        },
+                        _ => self.edits.push(Edit::ModifyItem {
+                            from,
+                            to,
+                            old: old.clone(),
+                            new: new.clone(),
+                        }),
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for edit in &self.edits {
+            writeln!(f, "{}", edit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays the first line of a node in its source form.
+struct Header<'a>(&'a Node);
+
+impl fmt::Display for Header<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_items = |f: &mut fmt::Formatter<'_>, items: &[Item]| {
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{}", item)?;
+            }
+            Ok(())
+        };
+        f.write_str("`")?;
+        match &self.0.kind {
+            NodeKind::Statement(statement) => write_items(f, &statement.signature)?,
+            NodeKind::Directive(directive) => {
+                write_items(f, &directive.signature)?;
+                f.write_str(":")?;
+                if !directive.arguments.is_empty() {
+                    f.write_str(" ")?;
+                    write_items(f, &directive.arguments)?;
+                }
+            },
+        }
+        f.write_str("`")
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Pair {
+    Same(usize, usize),
+    Moved(usize, usize),
+    Changed(usize, usize),
+    Removed(usize),
+    Inserted(usize),
+}
+
+/// Pair up old and new values.
+///
+/// Values with equal keys are paired in order of their longest common subsequence, and the
+/// remaining ones with equal keys as moves. Compatible leftovers between the same pairs are
+/// reported as changed.
+fn align<'a, T, K: PartialEq>(
+    old: &'a [T],
+    new: &'a [T],
+    key: impl Fn(&'a T) -> K,
+    is_compatible: impl Fn(&T, &T) -> bool,
+) -> Vec<Pair> {
+    let old_keys: Vec<K> = old.iter().map(&key).collect();
+    let new_keys: Vec<K> = new.iter().map(&key).collect();
+    let mut old_pairs: Vec<Option<Pair>> = old.iter().map(|_| None).collect();
+    let mut new_paired = vec![false; new.len()];
+
+    let anchors = longest_common_subsequence(&old_keys, &new_keys);
+    for &(old_index, new_index) in &anchors {
+        old_pairs[old_index] = Some(Pair::Same(old_index, new_index));
+        new_paired[new_index] = true;
+    }
+    for new_index in 0..new.len() {
+        if new_paired[new_index] {
+            continue;
+        }
+        let moved = (0..old.len())
+            .find(|&index| old_pairs[index].is_none() && old_keys[index] == new_keys[new_index]);
+        if let Some(old_index) = moved {
+            old_pairs[old_index] = Some(Pair::Moved(old_index, new_index));
+            new_paired[new_index] = true;
+        }
+    }
+    let mut start = (0, 0);
+    for end in anchors.iter().copied().chain([(old.len(), new.len())]) {
+        let mut old_index = start.0;
+        for new_index in start.1..end.1 {
+            if new_paired[new_index] {
+                continue;
+            }
+            let changed = (old_index..end.0).find(|&index| {
+                old_pairs[index].is_none() && is_compatible(&old[index], &new[new_index])
+            });
+            if let Some(index) = changed {
+                old_pairs[index] = Some(Pair::Changed(index, new_index));
+                new_paired[new_index] = true;
+                old_index = index + 1;
+            }
+        }
+        start = (end.0 + 1, end.1 + 1);
+    }
+
+    let mut pairs = Vec::with_capacity(old.len() + new.len());
+    for (index, pair) in old_pairs.into_iter().enumerate() {
+        pairs.push(pair.unwrap_or(Pair::Removed(index)));
+    }
+    for (index, paired) in new_paired.into_iter().enumerate() {
+        if !paired {
+            pairs.push(Pair::Inserted(index));
+        }
+    }
+    pairs
+}
+
+/// The pairs of equal values in a longest common subsequence.
+///
+/// Uses the linear space variant of Myers' algorithm, taking `O((n + m) * d)` time for `d`
+/// differences.
+fn longest_common_subsequence<K: PartialEq>(old: &[K], new: &[K]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    common_pairs(old, new, (0, 0), &mut pairs);
+    pairs
+}
+
+/// Push the common pairs of `old` and `new` in order, offset by the indices of their start.
+fn common_pairs<K: PartialEq>(
+    old: &[K],
+    new: &[K],
+    start: (usize, usize),
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    let prefix = old.iter().zip(new).take_while(|(old, new)| old == new).count();
+    pairs.extend((0..prefix).map(|index| (start.0 + index, start.1 + index)));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let start = (start.0 + prefix, start.1 + prefix);
+    let suffix = old.iter().rev()
+        .zip(new.iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+    if !old.is_empty() && !new.is_empty() {
+        let ((x, y), (u, v)) = middle_snake(old, new);
+        common_pairs(&old[..x], &new[..y], start, pairs);
+        pairs.extend((0..u - x).map(|index| (start.0 + x + index, start.1 + y + index)));
+        common_pairs(&old[u..], &new[v..], (start.0 + u, start.1 + v), pairs);
+    }
+    let end = (start.0 + old.len(), start.1 + new.len());
+    pairs.extend((0..suffix).map(|index| (end.0 + index, end.1 + index)));
+}
+
+/// The start and end of a run of equal values in the middle of a shortest edit script.
+///
+/// Both sequences must be non-empty and start and end with different values.
+fn middle_snake<K: PartialEq>(old: &[K], new: &[K]) -> ((usize, usize), (usize, usize)) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let limit = (n + m + 1) / 2;
+    let offset = limit + 1;
+    let at = |diagonal: isize| (diagonal + offset) as usize;
+    // The furthest `x` reached on each diagonal `x - y`, from the start of the sequences and
+    // from the start of the reversed sequences.
+    let mut forward = vec![0_isize; 2 * offset as usize + 1];
+    let mut backward = forward.clone();
+    for edits in 0..=limit {
+        for diagonal in (-edits..=edits).step_by(2) {
+            let (start, x) = follow(&mut forward, offset, diagonal, edits, (n, m), |x, y| {
+                old[x] == new[y]
+            });
+            let reversed = delta - diagonal;
+            if delta % 2 != 0
+                && (-(edits - 1)..=edits - 1).contains(&reversed)
+                && x + backward[at(reversed)] >= n
+            {
+                let (start_y, y) = (start - diagonal, x - diagonal);
+                return ((start as usize, start_y as usize), (x as usize, y as usize));
+            }
+        }
+        for diagonal in (-edits..=edits).step_by(2) {
+            let (start, x) = follow(&mut backward, offset, diagonal, edits, (n, m), |x, y| {
+                old[old.len() - 1 - x] == new[new.len() - 1 - y]
+            });
+            let forward_diagonal = delta - diagonal;
+            if delta % 2 == 0
+                && (-edits..=edits).contains(&forward_diagonal)
+                && forward[at(forward_diagonal)] + x >= n
+            {
+                let (start_y, y) = (start - diagonal, x - diagonal);
+                let begin = ((n - x) as usize, (m - y) as usize);
+                return (begin, ((n - start) as usize, (m - start_y) as usize));
+            }
+        }
+    }
+    unreachable!("paths from both ends overlap after at most half of the edits")
+}
+
+/// Take the furthest step with `edits` edits on `diagonal` and follow the equal values from
+/// there, returning the `x` before and after following them.
+fn follow(
+    furthest: &mut [isize],
+    offset: isize,
+    diagonal: isize,
+    edits: isize,
+    (n, m): (isize, isize),
+    is_equal: impl Fn(usize, usize) -> bool,
+) -> (isize, isize) {
+    let at = |diagonal: isize| (diagonal + offset) as usize;
+    let down = diagonal == -edits
+        || (diagonal != edits && furthest[at(diagonal - 1)] < furthest[at(diagonal + 1)]);
+    let start = if down { furthest[at(diagonal + 1)] } else { furthest[at(diagonal - 1)] + 1 };
+    let mut x = start;
+    while x < n && x - diagonal < m && is_equal(x as usize, (x - diagonal) as usize) {
+        x += 1;
+    }
+    furthest[at(diagonal)] = x;
+    (start, x)
+}
+
+fn signature(node: &Node) -> &[Item] {
+    match &node.kind {
+        NodeKind::Directive(directive) => &directive.signature,
+        NodeKind::Statement(statement) => &statement.signature,
+    }
+}
+
+/// An item without its locations, compared by kind and value.
+#[derive(PartialEq)]
+enum Key<'a> {
+    Word(&'a str),
+    Int(i32),
+    Float(u32),
+    Bool(bool),
+    Null,
+    Sigil(char, &'a str),
+    Group(char, Vec<Key<'a>>),
+    Pair(Box<Key<'a>>, char, Box<Key<'a>>),
+}
+
+fn node_key(node: &Node) -> (bool, Vec<Key<'_>>) {
+    (node.is_directive(), signature(node).iter().map(item_key).collect())
+}
+
+fn item_key(item: &Item) -> Key<'_> {
+    match &item.kind {
+        ItemKind::Word(word) => Key::Word(word),
+        ItemKind::Int(value) => Key::Int(*value),
+        ItemKind::Float(value) => Key::Float(value.to_bits()),
+        ItemKind::Bool(value) => Key::Bool(*value),
+        ItemKind::Null => Key::Null,
+        ItemKind::Sigil { sigil, name } => Key::Sigil(*sigil, name),
+        ItemKind::Pair { key, separator, value } => {
+            Key::Pair(Box::new(item_key(key)), *separator, Box::new(item_key(value)))
+        },
+        ItemKind::Parentheses(items) => Key::Group('(', items.iter().map(item_key).collect()),
+        ItemKind::Brackets(items) => Key::Group('[', items.iter().map(item_key).collect()),
+        ItemKind::Braces(items) => Key::Group('{', items.iter().map(item_key).collect()),
+    }
+}
+
+fn group_items(item: &Item) -> Option<(char, &[Item])> {
+    match &item.kind {
+        ItemKind::Parentheses(items) => Some(('(', items)),
+        ItemKind::Brackets(items) => Some(('[', items)),
+        ItemKind::Braces(items) => Some(('{', items)),
+        _ => None,
+    }
+}
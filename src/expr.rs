@@ -0,0 +1,298 @@
+//! Operator precedence parsing of [`Item`] lists into expression trees.
+//!
+//! Words found in the [`OperatorTable`] are operators, all other items are operands. Words
+//! consisting only of ASCII punctuation that are not in the table are reported as unknown
+//! operators. Parenthesized groups are parsed as nested expressions.
+//!
+//! Since words starting with `-` are parsed as numbers, `-` cannot be used as an operator.
+use smol_str::SmolStr;
+use src_ctx::{SourceError, Offset, Span};
+
+use crate::{Item, ItemKind};
+
+pub use self::eval::{Evaluator, Value, EvalError, EvalResult};
+
+
+mod eval;
+
+/// Type alias for [`Result`] with [`ExprError`].
+pub type ExprResult<T> = Result<T, SourceError<ExprError>>;
+
+/// Errors encountered during expression parsing.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ExprError {
+    #[error("Empty expression")]
+    EmptyExpression,
+    #[error("Expected a group")]
+    ExpectedGroup,
+    #[error("Missing operand for `{operator}`")]
+    MissingOperand { operator: SmolStr },
+    #[error("Missing operator before operand")]
+    MissingOperator,
+    #[error("Unknown operator `{operator}`")]
+    UnknownOperator { operator: SmolStr },
+    #[error("Operator `{operator}` cannot be chained")]
+    NonAssociative { operator: SmolStr },
+}
+
+/// Associativity of infix operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    /// `a + b + c` is parsed as `(a + b) + c`.
+    Left,
+    /// `a ^ b ^ c` is parsed as `a ^ (b ^ c)`.
+    Right,
+    /// `a < b < c` is an error.
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fixity {
+    Prefix,
+    Infix(Associativity),
+    Postfix,
+}
+
+/// Operators known to the expression parser, with their precedence.
+///
+/// Operators with higher precedence bind more tightly.
+#[derive(Debug, Clone, Default)]
+pub struct OperatorTable {
+    operators: Vec<(SmolStr, Fixity, u8)>,
+}
+
+impl OperatorTable {
+    /// Create an empty operator table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a prefix operator like `!a`.
+    pub fn prefix(mut self, symbol: &str, precedence: u8) -> Self {
+        self.operators.push((symbol.into(), Fixity::Prefix, precedence));
+        self
+    }
+
+    /// Add an infix operator like `a + b`.
+    pub fn infix(mut self, symbol: &str, precedence: u8, associativity: Associativity) -> Self {
+        self.operators.push((symbol.into(), Fixity::Infix(associativity), precedence));
+        self
+    }
+
+    /// Add a postfix operator like `a?`.
+    pub fn postfix(mut self, symbol: &str, precedence: u8) -> Self {
+        self.operators.push((symbol.into(), Fixity::Postfix, precedence));
+        self
+    }
+
+    /// Parse the items of a group [`Item`] into an expression.
+    pub fn parse_group(&self, group: &Item) -> ExprResult<Expr> {
+        let items = match &group.kind {
+            ItemKind::Parentheses(items) | ItemKind::Brackets(items) | ItemKind::Braces(items) => {
+                items
+            },
+            _ => return Err(SourceError::new(
+                ExprError::ExpectedGroup,
+                group.location.start(),
+                "expected group",
+            )),
+        };
+        self.parse_items(items, group.location.start())
+    }
+
+    /// Parse a list of items into an expression.
+    ///
+    /// The `location` is used for errors about empty expressions.
+    pub fn parse_items(&self, items: &[Item], location: Offset) -> ExprResult<Expr> {
+        if items.is_empty() {
+            return Err(SourceError::new(ExprError::EmptyExpression, location, "empty expression"));
+        }
+        let mut parser = Parser { table: self, items, index: 0 };
+        let expr = parser.expr(0)?;
+        if let Some(item) = parser.items.get(parser.index) {
+            return Err(parser.unexpected(item));
+        }
+        Ok(expr)
+    }
+
+    fn find(&self, symbol: &str, is_fixity: impl Fn(Fixity) -> bool) -> Option<(Fixity, u8)> {
+        self.operators.iter()
+            .find(|(known, fixity, _)| known == symbol && is_fixity(*fixity))
+            .map(|&(_, fixity, precedence)| (fixity, precedence))
+    }
+
+    fn is_operator(&self, symbol: &str) -> bool {
+        self.operators.iter().any(|(known, ..)| known == symbol)
+    }
+}
+
+/// An expression with a [`Span`] from its first to its last item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub location: Span,
+}
+
+/// The different kinds of [`Expr`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Operand(Item),
+    Prefix { operator: Operator, operand: Box<Expr> },
+    Infix { operator: Operator, left: Box<Expr>, right: Box<Expr> },
+    Postfix { operator: Operator, operand: Box<Expr> },
+}
+
+/// An operator used in an [`Expr`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operator {
+    pub symbol: SmolStr,
+    pub location: Span,
+}
+
+enum Token<'a> {
+    Operator(&'a str),
+    Unknown(&'a str),
+    Operand,
+}
+
+struct Parser<'t, 'i> {
+    table: &'t OperatorTable,
+    items: &'i [Item],
+    index: usize,
+}
+
+impl Parser<'_, '_> {
+    fn classify<'a>(&self, item: &'a Item) -> Token<'a> {
+        match item.word_str() {
+            Some(word) if self.table.is_operator(word) => Token::Operator(word),
+            Some(word) if word.chars().all(|c| c.is_ascii_punctuation()) => Token::Unknown(word),
+            _ => Token::Operand,
+        }
+    }
+
+    fn expr(&mut self, min_power: u16) -> ExprResult<Expr> {
+        let mut left = self.operand()?;
+        let mut last_non_associative = None;
+        while let Some(item) = self.items.get(self.index) {
+            let symbol = match self.classify(item) {
+                Token::Operator(symbol) => symbol,
+                Token::Unknown(symbol) => return Err(unknown_operator(item, symbol)),
+                Token::Operand => return Err(self.unexpected(item)),
+            };
+            let operator = Operator { symbol: symbol.into(), location: item.location };
+            if let Some((_, precedence)) = self.table.find(symbol, |f| f == Fixity::Postfix) {
+                if power(precedence) < min_power {
+                    break;
+                }
+                self.index += 1;
+                last_non_associative = None;
+                let location = left.location.start().span(item.location.end());
+                let kind = ExprKind::Postfix { operator, operand: Box::new(left) };
+                left = Expr { kind, location };
+            } else if let Some((Fixity::Infix(associativity), precedence)) =
+                self.table.find(symbol, |f| matches!(f, Fixity::Infix(_)))
+            {
+                let (left_power, right_power) = match associativity {
+                    Associativity::Left | Associativity::None => {
+                        (power(precedence), power(precedence) + 1)
+                    },
+                    Associativity::Right => (power(precedence) + 1, power(precedence)),
+                };
+                if left_power < min_power {
+                    break;
+                }
+                let is_non_associative = associativity == Associativity::None;
+                if is_non_associative && last_non_associative == Some(precedence) {
+                    return Err(SourceError::new(
+                        ExprError::NonAssociative { operator: symbol.into() },
+                        item.location.start(),
+                        "chained operator",
+                    ));
+                }
+                last_non_associative = is_non_associative.then_some(precedence);
+                self.index += 1;
+                let right = self.expr_after(right_power, &operator)?;
+                let location = left.location.start().span(right.location.end());
+                let kind = ExprKind::Infix {
+                    operator,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                };
+                left = Expr { kind, location };
+            } else {
+                return Err(self.unexpected(item));
+            }
+        }
+        Ok(left)
+    }
+
+    fn expr_after(&mut self, min_power: u16, operator: &Operator) -> ExprResult<Expr> {
+        if self.index < self.items.len() {
+            self.expr(min_power)
+        } else {
+            Err(SourceError::new(
+                ExprError::MissingOperand { operator: operator.symbol.clone() },
+                operator.location.start(),
+                "operand missing",
+            ))
+        }
+    }
+
+    fn operand(&mut self) -> ExprResult<Expr> {
+        let item = &self.items[self.index];
+        match self.classify(item) {
+            Token::Operator(symbol) => {
+                let operator = Operator { symbol: symbol.into(), location: item.location };
+                let Some((_, precedence)) = self.table.find(symbol, |f| f == Fixity::Prefix) else {
+                    return Err(SourceError::new(
+                        ExprError::MissingOperand { operator: operator.symbol },
+                        item.location.start(),
+                        "operand missing",
+                    ));
+                };
+                self.index += 1;
+                let operand = self.expr_after(power(precedence), &operator)?;
+                let location = item.location.start().span(operand.location.end());
+                let kind = ExprKind::Prefix { operator, operand: Box::new(operand) };
+                Ok(Expr { kind, location })
+            },
+            Token::Unknown(symbol) => Err(unknown_operator(item, symbol)),
+            Token::Operand => {
+                self.index += 1;
+                if let ItemKind::Parentheses(items) = &item.kind {
+                    self.table.parse_items(items, item.location.start())
+                } else {
+                    Ok(Expr { kind: ExprKind::Operand(item.clone()), location: item.location })
+                }
+            },
+        }
+    }
+
+    fn unexpected(&self, item: &Item) -> SourceError<ExprError> {
+        match self.classify(item) {
+            Token::Unknown(symbol) => unknown_operator(item, symbol),
+            Token::Operator(symbol) => SourceError::new(
+                ExprError::MissingOperand { operator: symbol.into() },
+                item.location.start(),
+                "operand missing",
+            ),
+            Token::Operand => SourceError::new(
+                ExprError::MissingOperator,
+                item.location.start(),
+                "operator missing",
+            ),
+        }
+    }
+}
+
+fn power(precedence: u8) -> u16 {
+    u16::from(precedence) * 2 + 1
+}
+
+fn unknown_operator(item: &Item, symbol: &str) -> SourceError<ExprError> {
+    SourceError::new(
+        ExprError::UnknownOperator { operator: symbol.into() },
+        item.location.start(),
+        "unknown operator",
+    )
+}
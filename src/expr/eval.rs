@@ -0,0 +1,236 @@
+use std::fmt;
+
+use smol_str::SmolStr;
+use src_ctx::{SourceError, Span};
+
+use crate::{Item, ItemKind, Directive};
+use super::{OperatorTable, Associativity, Expr, ExprKind, Operator};
+
+
+/// Type alias for [`Result`] with [`EvalError`].
+pub type EvalResult<T> = Result<T, SourceError<EvalError>>;
+
+/// Errors encountered during expression evaluation.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum EvalError {
+    #[error("Invalid expression: {0}")]
+    Expr(super::ExprError),
+    #[error("Item is not a constant value")]
+    NotConstant,
+    #[error("Unsupported operator `{operator}`")]
+    UnsupportedOperator { operator: SmolStr },
+    #[error("Invalid operand types for `{operator}`")]
+    TypeMismatch { operator: SmolStr },
+    #[error("Overflow in `{operator}`")]
+    Overflow { operator: SmolStr },
+    #[error("Division by zero")]
+    DivisionByZero,
+}
+
+/// The result of evaluating an [`Expr`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{}", ItemKind::Float(*value)),
+            Self::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Evaluates constant arithmetic and logic expressions.
+///
+/// Supported are the infix operators `||`, `&&`, `==`, `!=`, `<`, `<=`, `>`, `>=`, `+`, `sub`,
+/// `*`, `/` and `%`, from lowest to highest precedence, and the prefix operators `!` and `neg`.
+/// Integers are converted to floats when mixed with floats.
+///
+/// Since `-` starts a number, subtraction and negation use the words `sub` and `neg`.
+#[derive(Debug, Clone)]
+pub struct Evaluator {
+    operators: OperatorTable,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        let mut operators = OperatorTable::new()
+            .infix("||", 0, Associativity::Left)
+            .infix("&&", 1, Associativity::Left);
+        for symbol in ["==", "!=", "<", "<=", ">", ">="] {
+            operators = operators.infix(symbol, 2, Associativity::None);
+        }
+        let operators = operators
+            .infix("+", 3, Associativity::Left)
+            .infix("sub", 3, Associativity::Left)
+            .infix("*", 4, Associativity::Left)
+            .infix("/", 4, Associativity::Left)
+            .infix("%", 4, Associativity::Left)
+            .prefix("!", 5)
+            .prefix("neg", 5);
+        Self { operators }
+    }
+}
+
+impl Evaluator {
+    /// Create an evaluator with the default operators.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The [`OperatorTable`] used to parse groups.
+    pub fn operators(&self) -> &OperatorTable {
+        &self.operators
+    }
+
+    /// Evaluate all arguments of a [`Directive`].
+    pub fn evaluate_arguments(&self, directive: &Directive) -> EvalResult<Vec<Value>> {
+        directive.arguments.iter().map(|item| self.evaluate_item(item)).collect()
+    }
+
+    /// Evaluate a number, or the expression inside of a parenthesized group.
+    pub fn evaluate_item(&self, item: &Item) -> EvalResult<Value> {
+        match &item.kind {
+            ItemKind::Int(value) => Ok(Value::Int(*value)),
+            ItemKind::Float(value) => Ok(Value::Float(*value)),
+            ItemKind::Bool(value) => Ok(Value::Bool(*value)),
+            ItemKind::Parentheses(items) => {
+                let expr = self.operators.parse_items(items, item.location.start())
+                    .map_err(|error| SourceError::new(
+                        EvalError::Expr(error.error().clone()),
+                        error.offset(),
+                        "invalid expression",
+                    ))?;
+                self.evaluate(&expr)
+            },
+            _ => Err(error_at(EvalError::NotConstant, item.location, "not constant")),
+        }
+    }
+
+    /// Evaluate a parsed [`Expr`].
+    pub fn evaluate(&self, expr: &Expr) -> EvalResult<Value> {
+        match &expr.kind {
+            ExprKind::Operand(item) => self.evaluate_item(item),
+            ExprKind::Prefix { operator, operand } => {
+                let operand = self.evaluate(operand)?;
+                match (operator.symbol.as_str(), operand) {
+                    ("!", Value::Bool(value)) => Ok(Value::Bool(!value)),
+                    ("!", _) => Err(type_mismatch(operator)),
+                    ("neg", Value::Int(value)) => {
+                        value.checked_neg().map(Value::Int).ok_or_else(|| overflow(operator))
+                    },
+                    ("neg", Value::Float(value)) => Ok(Value::Float(-value)),
+                    ("neg", _) => Err(type_mismatch(operator)),
+                    _ => Err(unsupported(operator)),
+                }
+            },
+            ExprKind::Infix { operator, left, right } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                binary(operator, left, right)
+            },
+            ExprKind::Postfix { operator, .. } => Err(unsupported(operator)),
+        }
+    }
+}
+
+fn binary(operator: &Operator, left: Value, right: Value) -> EvalResult<Value> {
+    let symbol = operator.symbol.as_str();
+    match (left, right) {
+        (Value::Bool(left), Value::Bool(right)) => match symbol {
+            "||" => Ok(Value::Bool(left || right)),
+            "&&" => Ok(Value::Bool(left && right)),
+            "==" => Ok(Value::Bool(left == right)),
+            "!=" => Ok(Value::Bool(left != right)),
+            _ => Err(type_mismatch(operator)),
+        },
+        (Value::Int(left), Value::Int(right)) => {
+            if let Some(result) = compare(symbol, &left, &right) {
+                return Ok(Value::Bool(result));
+            }
+            if (symbol == "/" || symbol == "%") && right == 0 {
+                return Err(division_by_zero(operator));
+            }
+            let result = match symbol {
+                "+" => left.checked_add(right),
+                "sub" => left.checked_sub(right),
+                "*" => left.checked_mul(right),
+                "/" => left.checked_div(right),
+                "%" => left.checked_rem(right),
+                _ => return Err(type_mismatch(operator)),
+            };
+            result.map(Value::Int).ok_or_else(|| overflow(operator))
+        },
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            let (left, right) = (float(left), float(right));
+            if let Some(result) = compare(symbol, &left, &right) {
+                return Ok(Value::Bool(result));
+            }
+            if (symbol == "/" || symbol == "%") && right == 0.0 {
+                return Err(division_by_zero(operator));
+            }
+            let result = match symbol {
+                "+" => left + right,
+                "sub" => left - right,
+                "*" => left * right,
+                "/" => left / right,
+                "%" => left % right,
+                _ => return Err(type_mismatch(operator)),
+            };
+            if result.is_finite() {
+                Ok(Value::Float(result))
+            } else {
+                Err(overflow(operator))
+            }
+        },
+        _ => Err(type_mismatch(operator)),
+    }
+}
+
+fn compare<T: PartialOrd>(symbol: &str, left: &T, right: &T) -> Option<bool> {
+    match symbol {
+        "==" => Some(left == right),
+        "!=" => Some(left != right),
+        "<" => Some(left < right),
+        "<=" => Some(left <= right),
+        ">" => Some(left > right),
+        ">=" => Some(left >= right),
+        _ => None,
+    }
+}
+
+fn float(value: Value) -> f32 {
+    match value {
+        Value::Int(value) => value as f32,
+        Value::Float(value) => value,
+        Value::Bool(_) => unreachable!("booleans are not numbers"),
+    }
+}
+
+fn error_at(error: EvalError, location: Span, label: &'static str) -> SourceError<EvalError> {
+    SourceError::new(error, location.start(), label)
+}
+
+fn type_mismatch(operator: &Operator) -> SourceError<EvalError> {
+    let error = EvalError::TypeMismatch { operator: operator.symbol.clone() };
+    error_at(error, operator.location, "invalid operand types")
+}
+
+fn division_by_zero(operator: &Operator) -> SourceError<EvalError> {
+    error_at(EvalError::DivisionByZero, operator.location, "division by zero")
+}
+
+fn overflow(operator: &Operator) -> SourceError<EvalError> {
+    let error = EvalError::Overflow { operator: operator.symbol.clone() };
+    error_at(error, operator.location, "overflow")
+}
+
+fn unsupported(operator: &Operator) -> SourceError<EvalError> {
+    let error = EvalError::UnsupportedOperator { operator: operator.symbol.clone() };
+    error_at(error, operator.location, "unsupported operator")
+}
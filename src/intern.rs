@@ -0,0 +1,276 @@
+//! A variant of [`Tree`](crate::Tree) with words interned as [`Symbol`] values.
+//!
+//! An [`Interner`] can be shared across any number of parses, so the same word gets the same
+//! [`Symbol`] in all resulting trees and can be compared without looking at its text.
+use std::collections::HashMap;
+
+use smol_str::SmolStr;
+use src_ctx::{Input, Offset, Span};
+
+use crate::{Indent, ParseOptions, ParseResult, Doc, Comment};
+use crate::parse::parse_interned;
+
+
+/// A compact identifier for a word in an [`Interner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// The position of the symbol in its interner, in order of first occurrence.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Maps words to [`Symbol`] values and back.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    words: Vec<SmolStr>,
+    symbols: HashMap<SmolStr, Symbol>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The [`Symbol`] for a word, adding the word if it is not known yet.
+    pub fn intern(&mut self, word: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(word) {
+            return symbol;
+        }
+        let symbol = Symbol(self.words.len().try_into().expect("too many interned words"));
+        let word = SmolStr::from(word);
+        self.words.push(word.clone());
+        self.symbols.insert(word, symbol);
+        symbol
+    }
+
+    /// The [`Symbol`] for a word, if it is known.
+    pub fn get(&self, word: &str) -> Option<Symbol> {
+        self.symbols.get(word).copied()
+    }
+
+    /// The word of a [`Symbol`].
+    ///
+    /// Panics if the symbol was not created by this interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.words[symbol.index()]
+    }
+
+    /// The number of known words.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns `true` if no words are known.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// A collection of [`Node`] roots with interned words.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Tree {
+    pub roots: Vec<Node>,
+}
+
+impl Tree {
+    /// Try to parse a tree assuming the given [`Indent`], interning words with `interner`.
+    pub fn parse(input: Input<'_>, indent: Indent, interner: &mut Interner) -> ParseResult<Self> {
+        parse_interned(input, ParseOptions::new(indent), interner)
+    }
+
+    /// Try to parse a tree with the given [`ParseOptions`], interning words with `interner`.
+    pub fn parse_with(
+        input: Input<'_>,
+        options: ParseOptions,
+        interner: &mut Interner,
+    ) -> ParseResult<Self> {
+        parse_interned(input, options, interner)
+    }
+
+    /// Convert into an owned [`Tree`](crate::Tree) using the interner used for parsing.
+    pub fn resolve(&self, interner: &Interner) -> crate::Tree {
+        crate::Tree { roots: self.roots.iter().map(|node| node.resolve(interner)).collect() }
+    }
+}
+
+impl std::ops::Deref for Tree {
+    type Target = Vec<Node>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.roots
+    }
+}
+
+/// A parsed node in a [`Tree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub location: Offset,
+    pub docs: Vec<Doc>,
+    pub comment: Option<Comment>,
+}
+
+impl Node {
+    /// Get a slice of children independent of the [`NodeKind`].
+    pub fn children(&self) -> &[Self] {
+        match &self.kind {
+            NodeKind::Directive(directive) => &directive.children,
+            NodeKind::Statement(_) => &[],
+        }
+    }
+
+    /// Convert into an owned [`Node`](crate::Node) using the interner used for parsing.
+    pub fn resolve(&self, interner: &Interner) -> crate::Node {
+        let kind = match &self.kind {
+            NodeKind::Directive(directive) => crate::NodeKind::Directive(crate::Directive {
+                signature: resolve_items(&directive.signature, interner),
+                arguments: resolve_items(&directive.arguments, interner),
+                children: directive.children.iter().map(|node| node.resolve(interner)).collect(),
+            }),
+            NodeKind::Statement(statement) => crate::NodeKind::Statement(crate::Statement {
+                signature: resolve_items(&statement.signature, interner),
+            }),
+        };
+        crate::Node {
+            kind,
+            location: self.location,
+            docs: self.docs.clone(),
+            comment: self.comment.clone(),
+        }
+    }
+}
+
+impl std::ops::Deref for Node {
+    type Target = NodeKind;
+
+    fn deref(&self) -> &Self::Target {
+        &self.kind
+    }
+}
+
+/// Data for a [`NodeKind::Directive`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+    pub signature: Vec<Item>,
+    pub arguments: Vec<Item>,
+    pub children: Vec<Node>,
+}
+
+/// Data for a [`NodeKind::Statement`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub signature: Vec<Item>,
+}
+
+/// The different kinds of [`Node`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    Directive(Directive),
+    Statement(Statement),
+}
+
+impl NodeKind {
+    fn_enum_is_variant!(is_directive, Directive);
+    fn_enum_is_variant!(is_statement, Statement);
+
+    fn_enum_try_into_variant!(try_into_directive, Directive, Directive);
+    fn_enum_try_into_variant!(try_into_statement, Statement, Statement);
+
+    fn_enum_variant_access!(directive -> &Directive, Self::Directive(directive) => directive);
+    fn_enum_variant_access!(statement -> &Statement, Self::Statement(statement) => statement);
+}
+
+/// An item of [`ItemKind`] found in a [`Statement`] or [`Directive`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub location: Span,
+}
+
+impl Item {
+    /// Convert into an owned [`Item`](crate::Item) using the interner used for parsing.
+    pub fn resolve(&self, interner: &Interner) -> crate::Item {
+        let kind = match &self.kind {
+            ItemKind::Word(symbol) => crate::ItemKind::Word(interner.resolve(*symbol).into()),
+            ItemKind::Int(value) => crate::ItemKind::Int(*value),
+            ItemKind::Float(value) => crate::ItemKind::Float(*value),
+            ItemKind::Bool(value) => crate::ItemKind::Bool(*value),
+            ItemKind::Null => crate::ItemKind::Null,
+            ItemKind::Sigil { sigil, name } => crate::ItemKind::Sigil {
+                sigil: *sigil,
+                name: interner.resolve(*name).into(),
+            },
+            ItemKind::Parentheses(items) => {
+                crate::ItemKind::Parentheses(resolve_items(items, interner))
+            },
+            ItemKind::Brackets(items) => crate::ItemKind::Brackets(resolve_items(items, interner)),
+            ItemKind::Braces(items) => crate::ItemKind::Braces(resolve_items(items, interner)),
+            ItemKind::Pair { key, separator, value } => crate::ItemKind::Pair {
+                key: Box::new(key.resolve(interner)),
+                separator: *separator,
+                value: Box::new(value.resolve(interner)),
+            },
+        };
+        crate::Item { kind, location: self.location }
+    }
+}
+
+impl std::ops::Deref for Item {
+    type Target = ItemKind;
+
+    fn deref(&self) -> &Self::Target {
+        &self.kind
+    }
+}
+
+/// The different kinds of [`Item`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind {
+    Word(Symbol),
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    Null,
+    Sigil { sigil: char, name: Symbol },
+    Parentheses(Vec<Item>),
+    Brackets(Vec<Item>),
+    Braces(Vec<Item>),
+    Pair { key: Box<Item>, separator: char, value: Box<Item> },
+}
+
+impl ItemKind {
+    fn_enum_is_variant!(is_word, Word);
+    fn_enum_is_variant!(is_int, Int);
+    fn_enum_is_variant!(is_float, Float);
+    fn_enum_is_variant!(is_bool, Bool);
+    fn_enum_is_variant!(is_null, Null);
+    fn_enum_is_variant!(is_sigil, Sigil);
+    fn_enum_is_variant!(is_parenthesized, Parentheses);
+    fn_enum_is_variant!(is_bracketed, Brackets);
+    fn_enum_is_variant!(is_braced, Braces);
+    fn_enum_is_variant!(is_pair, Pair);
+
+    fn_enum_try_into_variant!(try_into_word, Word, Symbol);
+    fn_enum_try_into_variant!(try_into_int, Int, i32);
+    fn_enum_try_into_variant!(try_into_float, Float, f32);
+    fn_enum_try_into_variant!(try_into_bool, Bool, bool);
+    fn_enum_try_into_variant!(try_into_parenthesized, Parentheses, Vec<Item>);
+    fn_enum_try_into_variant!(try_into_bracketed, Brackets, Vec<Item>);
+    fn_enum_try_into_variant!(try_into_braced, Braces, Vec<Item>);
+
+    fn_enum_variant_access!(word -> Symbol, Self::Word(symbol) => *symbol);
+    fn_enum_variant_access!(int -> i32, Self::Int(value) => *value);
+    fn_enum_variant_access!(float -> f32, Self::Float(value) => *value);
+    fn_enum_variant_access!(bool -> bool, Self::Bool(value) => *value);
+    fn_enum_variant_access!(parenthesized -> &[Item], Self::Parentheses(items) => items);
+    fn_enum_variant_access!(bracketed -> &[Item], Self::Brackets(items) => items);
+    fn_enum_variant_access!(braced -> &[Item], Self::Braces(items) => items);
+    fn_enum_variant_access!(pair -> (&Item, &Item), Self::Pair { key, value, .. } => (key, value));
+}
+
+fn resolve_items(items: &[Item], interner: &Interner) -> Vec<crate::Item> {
+    items.iter().map(|item| item.resolve(interner)).collect()
+}
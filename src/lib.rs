@@ -2,38 +2,11 @@
 use smol_str::SmolStr;
 
 pub use parse::*;
+pub use locate::*;
 use src_ctx::{Input, Offset, Span};
 
 
-mod parse;
-
-/// A collection of [`Node`] roots.
-#[derive(Debug, Clone, PartialEq, Default)]
-pub struct Tree {
-    pub roots: Vec<Node>,
-}
-
-impl Tree {
-    /// Try to parse a tree from a `&str` assuming the given [`Indent`].
-    pub fn parse(input: Input<'_>, indent: Indent) -> ParseResult<Self> {
-        parse_input(input, indent)
-    }
-}
-
-impl std::ops::Deref for Tree {
-    type Target = Vec<Node>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.roots
-    }
-}
-
-impl std::ops::DerefMut for Tree {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.roots
-    }
-}
-
+// Defined before the modules so all tree representations can use them.
 macro_rules! fn_enum_is_variant {
     ($name:ident, $variant:ident $(,)?) => {
         pub fn $name(&self) -> bool {
@@ -66,11 +39,100 @@ macro_rules! fn_enum_variant_access {
     }
 }
 
+
+mod parse;
+mod locate;
+mod print;
+
+pub mod sexpr;
+pub mod expr;
+pub mod transform;
+pub mod merge;
+pub mod diff;
+pub mod structural;
+pub mod arena;
+pub mod borrowed;
+pub mod intern;
+pub mod rewrite;
+
+#[cfg(feature = "json")]
+pub mod convert;
+
+/// A collection of [`Node`] roots.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Tree {
+    pub roots: Vec<Node>,
+}
+
+impl Tree {
+    /// Try to parse a tree from a `&str` assuming the given [`Indent`].
+    pub fn parse(input: Input<'_>, indent: Indent) -> ParseResult<Self> {
+        parse_input(input, indent)
+    }
+
+    /// Try to parse a tree from a `&str` with the given [`ParseOptions`].
+    pub fn parse_with(input: Input<'_>, options: ParseOptions) -> ParseResult<Self> {
+        parse_input_with(input, options)
+    }
+
+    /// Try to parse `new` after applying `edit` to the `old` input this tree was parsed from
+    /// with the same [`ParseOptions`].
+    ///
+    /// Only the roots touched by the edit are parsed again, all other nodes are reused with
+    /// their locations moved to `new`. The result is the same as parsing `new` from scratch.
+    pub fn reparse(
+        &self,
+        old: Input<'_>,
+        edit: &TextEdit,
+        new: Input<'_>,
+        options: ParseOptions,
+    ) -> ParseResult<Self> {
+        reparse_input(self, old, edit, new, options)
+    }
+
+    /// Try to parse a tree like [`Tree::parse`], splitting the input at root nodes to parse
+    /// the parts concurrently.
+    ///
+    /// The result, including any error, is the same as with [`Tree::parse`].
+    #[cfg(feature = "rayon")]
+    pub fn parse_split(input: Input<'_>, indent: Indent) -> ParseResult<Self> {
+        parse_split_input(input, ParseOptions::new(indent))
+    }
+
+    /// Try to parse a tree like [`Tree::parse_with`], splitting the input at root nodes to
+    /// parse the parts concurrently.
+    ///
+    /// The result, including any error, is the same as with [`Tree::parse_with`].
+    #[cfg(feature = "rayon")]
+    pub fn parse_split_with(input: Input<'_>, options: ParseOptions) -> ParseResult<Self> {
+        parse_split_input(input, options)
+    }
+}
+
+impl std::ops::Deref for Tree {
+    type Target = Vec<Node>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.roots
+    }
+}
+
+impl std::ops::DerefMut for Tree {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.roots
+    }
+}
+
 /// A parsed node in a [`Tree`] with a specific [`NodeKind`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct Node {
     pub kind: NodeKind,
     pub location: Offset,
+    /// The doc comments on the lines preceding the node.
+    pub docs: Vec<Doc>,
+    /// The comment at the end of the node's line, if enabled with
+    /// [`ParseOptions::trailing_comments`].
+    pub comment: Option<Comment>,
 }
 
 impl Node {
@@ -93,6 +155,24 @@ impl std::ops::Deref for Node {
     }
 }
 
+/// A doc comment line starting with `;;`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Doc {
+    /// The text following the `;;` marker and a single space.
+    pub text: SmolStr,
+    /// The location of the whole comment, including the marker.
+    pub location: Span,
+}
+
+/// A comment starting with `;` at the end of a node's line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The text following the `;` marker and a single space.
+    pub text: SmolStr,
+    /// The location of the whole comment, including the marker.
+    pub location: Span,
+}
+
 /// Data for a [`NodeKind::Directive`] in a [`Tree`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct Directive {
@@ -146,22 +226,39 @@ pub enum ItemKind {
     Word(SmolStr),
     Int(i32),
     Float(f32),
+    /// A word from the `true` or `false` set of [`ParseOptions::literals`].
+    Bool(bool),
+    /// A word from the `null` set of [`ParseOptions::literals`].
+    Null,
+    /// A word starting with one of the [`ParseOptions::sigils`].
+    Sigil { sigil: char, name: SmolStr },
     Parentheses(Vec<Item>),
     Brackets(Vec<Item>),
     Braces(Vec<Item>),
+    /// A word key and an item value joined by the separator set with
+    /// [`ParseOptions::pair_separator`].
+    ///
+    /// The location of a pair covers the key and separator, like the location of a group covers
+    /// its opening character.
+    Pair { key: Box<Item>, separator: char, value: Box<Item> },
 }
 
 impl ItemKind {
     fn_enum_is_variant!(is_word, Word);
     fn_enum_is_variant!(is_int, Int);
     fn_enum_is_variant!(is_float, Float);
+    fn_enum_is_variant!(is_bool, Bool);
+    fn_enum_is_variant!(is_null, Null);
+    fn_enum_is_variant!(is_sigil, Sigil);
     fn_enum_is_variant!(is_parenthesized, Parentheses);
     fn_enum_is_variant!(is_bracketed, Brackets);
     fn_enum_is_variant!(is_braced, Braces);
+    fn_enum_is_variant!(is_pair, Pair);
 
     fn_enum_try_into_variant!(try_into_word, Word, SmolStr);
     fn_enum_try_into_variant!(try_into_int, Int, i32);
     fn_enum_try_into_variant!(try_into_float, Float, f32);
+    fn_enum_try_into_variant!(try_into_bool, Bool, bool);
     fn_enum_try_into_variant!(try_into_parenthesized, Parentheses, Vec<Item>);
     fn_enum_try_into_variant!(try_into_bracketed, Brackets, Vec<Item>);
     fn_enum_try_into_variant!(try_into_braced, Braces, Vec<Item>);
@@ -170,7 +267,17 @@ impl ItemKind {
     fn_enum_variant_access!(word_str -> &str, Self::Word(word) => word.as_str());
     fn_enum_variant_access!(int -> i32, Self::Int(value) => *value);
     fn_enum_variant_access!(float -> f32, Self::Float(value) => *value);
+    fn_enum_variant_access!(bool -> bool, Self::Bool(value) => *value);
     fn_enum_variant_access!(parenthesized -> &[Item], Self::Parentheses(items) => items);
     fn_enum_variant_access!(bracketed -> &[Item], Self::Brackets(items) => items);
     fn_enum_variant_access!(braced -> &[Item], Self::Braces(items) => items);
+    fn_enum_variant_access!(pair -> (&Item, &Item), Self::Pair { key, value, .. } => (key, value));
+
+    /// The name following `sigil` in a sigil item.
+    pub fn sigil_name(&self, sigil: char) -> Option<&str> {
+        match self {
+            Self::Sigil { sigil: item_sigil, name } if *item_sigil == sigil => Some(name),
+            _ => None,
+        }
+    }
 }
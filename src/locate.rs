@@ -0,0 +1,50 @@
+use src_ctx::{Input, Offset, Span};
+
+
+/// Resolves [`Offset`] and [`Span`] locations to byte indices into an [`Input`].
+///
+/// Lookups scan forward from the previously resolved location, so resolving locations in
+/// source order (as found in a [`Tree`](crate::Tree)) is linear in the input length.
+#[derive(Clone)]
+pub struct Locator<'a> {
+    input: Input<'a>,
+    cursor: usize,
+}
+
+impl<'a> Locator<'a> {
+    /// Create a locator for offsets within `input`.
+    pub fn new(input: Input<'a>) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    /// The input locations are resolved against.
+    pub fn input(&self) -> &Input<'a> {
+        &self.input
+    }
+
+    /// Find the byte index of `offset` in the input.
+    ///
+    /// Returns `None` if the offset is not part of the input.
+    pub fn byte_index(&mut self, offset: Offset) -> Option<usize> {
+        self.scan_from(self.cursor, offset)
+            .or_else(|| self.scan_from(0, offset))
+    }
+
+    /// Find the byte range of `span` in the input.
+    ///
+    /// Returns `None` if the span is not part of the input.
+    pub fn byte_range(&mut self, span: Span) -> Option<std::ops::Range<usize>> {
+        let start = self.byte_index(span.start())?;
+        let end = self.byte_index(span.end())?;
+        Some(start..end)
+    }
+
+    fn scan_from(&mut self, mut index: usize, offset: Offset) -> Option<usize> {
+        let content = self.input.content();
+        while self.input.skip(index).offset() != offset {
+            index += content[index..].chars().next()?.len_utf8();
+        }
+        self.cursor = index;
+        Some(index)
+    }
+}
@@ -0,0 +1,240 @@
+//! Merging of an overlay [`Tree`] into a base [`Tree`].
+//!
+//! Nodes are matched by their signature, compared by its printed form. Each overlay node is
+//! combined with the first matching node according to the [`Strategy`] configured for its
+//! path, or appended when no node matches.
+use smol_str::SmolStr;
+use src_ctx::Offset;
+
+use crate::{Tree, Node, NodeKind, Directive, Item};
+
+
+/// How an overlay node is combined with a matching base node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// The overlay node replaces the base node.
+    Replace,
+    /// The overlay children are appended to the base children.
+    AppendChildren,
+    /// The children are merged recursively.
+    DeepMerge,
+    /// The base node is removed.
+    Delete,
+}
+
+/// Where a node in a [`Merged`] tree came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeSource {
+    Base(Offset),
+    /// The node comes from the overlay, including overlay nodes merged into an earlier overlay
+    /// node with the same signature.
+    Overlay(Offset),
+    /// The node keeps the base location, with arguments or children from the overlay.
+    Merged { base: Offset, overlay: Offset },
+}
+
+/// The [`NodeSource`] of the node at `path`, given as child indices starting at the roots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    pub path: Vec<usize>,
+    pub source: NodeSource,
+}
+
+/// The result of [`Merge::apply`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub tree: Tree,
+    /// The sources of all nodes in pre-order.
+    pub provenance: Vec<Provenance>,
+}
+
+/// Merge configuration.
+///
+/// Directive arguments, doc comments and trailing comments from the overlay replace those of
+/// the base node unless they are empty.
+#[derive(Debug, Clone)]
+pub struct Merge {
+    default: Strategy,
+    paths: Vec<(Vec<SmolStr>, Strategy)>,
+    delete_marker: Option<SmolStr>,
+}
+
+impl Default for Merge {
+    fn default() -> Self {
+        Self::new(Strategy::DeepMerge)
+    }
+}
+
+impl Merge {
+    /// Create a configuration using the given default [`Strategy`].
+    pub fn new(default: Strategy) -> Self {
+        Self { default, paths: Vec::new(), delete_marker: None }
+    }
+
+    /// Use a [`Strategy`] for the overlay nodes at a path of signatures.
+    ///
+    /// The path `["server", "tls"]` selects `tls` directives inside of `server` directives.
+    pub fn strategy(mut self, path: &[&str], strategy: Strategy) -> Self {
+        self.paths.push((path.iter().map(|&key| key.into()).collect(), strategy));
+        self
+    }
+
+    /// Treat overlay nodes starting with the `marker` word as deleting the nodes matching the
+    /// rest of their signature.
+    pub fn delete_marker(mut self, marker: &str) -> Self {
+        self.delete_marker = Some(marker.into());
+        self
+    }
+
+    /// Merge `overlay` into `base`.
+    pub fn apply(&self, base: &Tree, overlay: &Tree) -> Merged {
+        let entries = base.iter().map(Entry::base).collect();
+        let entries = self.merge(entries, overlay, &mut Vec::new());
+        let mut provenance = Vec::new();
+        let mut path = Vec::new();
+        let roots = entries.into_iter().enumerate()
+            .map(|(index, entry)| {
+                path.push(index);
+                let node = entry.into_node(&mut path, &mut provenance);
+                path.pop();
+                node
+            })
+            .collect();
+        Merged { tree: Tree { roots }, provenance }
+    }
+
+    fn strategy_for(&self, path: &[SmolStr]) -> Strategy {
+        self.paths.iter()
+            .find(|(known, _)| known == path)
+            .map_or(self.default, |&(_, strategy)| strategy)
+    }
+
+    fn is_delete_marker(&self, item: &Item) -> bool {
+        self.delete_marker.is_some() && item.word() == self.delete_marker.as_ref()
+    }
+
+    fn merge(
+        &self,
+        mut entries: Vec<Entry>,
+        overlay: &[Node],
+        path: &mut Vec<SmolStr>,
+    ) -> Vec<Entry> {
+        for node in overlay {
+            let signature = signature(node);
+            if let [marker, rest @ ..] = signature {
+                if self.is_delete_marker(marker) {
+                    let key = key(rest);
+                    entries.retain(|entry| entry.key != key);
+                    continue;
+                }
+            }
+            let key = key(signature);
+            path.push(key.clone());
+            let strategy = self.strategy_for(path);
+            let position = entries.iter().position(|entry| entry.key == key);
+            match (strategy, position) {
+                (Strategy::Delete, _) => entries.retain(|entry| entry.key != key),
+                (_, None) => entries.push(Entry::overlay(node, key)),
+                (Strategy::Replace, Some(index)) => entries[index] = Entry::overlay(node, key),
+                (Strategy::AppendChildren | Strategy::DeepMerge, Some(index)) => {
+                    let entry = &mut entries[index];
+                    match (&mut entry.node.kind, &node.kind) {
+                        (NodeKind::Directive(current), NodeKind::Directive(directive)) => {
+                            if !directive.arguments.is_empty() {
+                                current.arguments = directive.arguments.clone();
+                            }
+                            if !node.docs.is_empty() {
+                                entry.node.docs = node.docs.clone();
+                            }
+                            if node.comment.is_some() {
+                                entry.node.comment = node.comment.clone();
+                            }
+                            if strategy == Strategy::DeepMerge {
+                                let children = std::mem::take(&mut entry.children);
+                                entry.children = self.merge(children, &directive.children, path);
+                            } else {
+                                let children = directive.children.iter()
+                                    .map(|child| Entry::overlay(child, node_key(child)));
+                                entry.children.extend(children);
+                            }
+                            if let NodeSource::Base(base) | NodeSource::Merged { base, .. } =
+                                entry.source
+                            {
+                                entry.source = NodeSource::Merged { base, overlay: node.location };
+                            }
+                        },
+                        _ => *entry = Entry::overlay(node, key),
+                    }
+                },
+            }
+            path.pop();
+        }
+        entries
+    }
+}
+
+/// A node being merged, with its children kept separately to track their sources.
+struct Entry {
+    node: Node,
+    key: SmolStr,
+    source: NodeSource,
+    children: Vec<Entry>,
+}
+
+impl Entry {
+    fn new(node: &Node, key: SmolStr, source: fn(Offset) -> NodeSource) -> Self {
+        let mut node = node.clone();
+        let children = match &mut node.kind {
+            NodeKind::Directive(directive) => std::mem::take(&mut directive.children),
+            NodeKind::Statement(_) => Vec::new(),
+        };
+        Self {
+            source: source(node.location),
+            children: children.iter()
+                .map(|child| Self::new(child, node_key(child), source))
+                .collect(),
+            node,
+            key,
+        }
+    }
+
+    fn base(node: &Node) -> Self {
+        Self::new(node, node_key(node), NodeSource::Base)
+    }
+
+    fn overlay(node: &Node, key: SmolStr) -> Self {
+        Self::new(node, key, NodeSource::Overlay)
+    }
+
+    fn into_node(self, path: &mut Vec<usize>, provenance: &mut Vec<Provenance>) -> Node {
+        provenance.push(Provenance { path: path.clone(), source: self.source });
+        let mut node = self.node;
+        let children = self.children.into_iter().enumerate()
+            .map(|(index, child)| {
+                path.push(index);
+                let child = child.into_node(path, provenance);
+                path.pop();
+                child
+            })
+            .collect();
+        if let NodeKind::Directive(Directive { children: slot, .. }) = &mut node.kind {
+            *slot = children;
+        }
+        node
+    }
+}
+
+fn signature(node: &Node) -> &[Item] {
+    match &node.kind {
+        NodeKind::Directive(directive) => &directive.signature,
+        NodeKind::Statement(statement) => &statement.signature,
+    }
+}
+
+fn node_key(node: &Node) -> SmolStr {
+    key(signature(node))
+}
+
+fn key(items: &[Item]) -> SmolStr {
+    items.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ").into()
+}
@@ -1,19 +1,34 @@
 use smol_str::SmolStr;
-use src_ctx::{Input, SourceError, Offset};
+use src_ctx::{Input, SourceError, Offset, Span};
 
-use crate::{
-    Tree, Item, Node, NodeKind, ItemKind, Statement, Directive,
-};
+use crate::{Tree, ItemKind, borrowed, intern};
 
 use self::input::InputExt;
+use self::build::{Build, Owned, Borrowed, Interned};
+
+pub use self::incremental::TextEdit;
+pub(crate) use self::incremental::reparse_input;
+#[cfg(feature = "rayon")]
+pub use self::parallel::parse_all;
+#[cfg(feature = "rayon")]
+pub(crate) use self::parallel::parse_split_input;
 
 
 mod input;
+mod incremental;
+mod build;
+#[cfg(feature = "rayon")]
+mod parallel;
 
 mod token {
-    use super::{ItemKind, Group};
+    use super::{GroupKind, Group};
 
     pub const COMMENT: char = ';';
+    pub const DOC_COMMENT: &str = ";;";
+    // Lines starting with these were unclosed groups before, so no valid source changes meaning.
+    pub const BLOCK_COMMENT_OPEN: &str = "(;";
+    pub const BLOCK_COMMENT_CLOSE: &str = ";)";
+    pub const DISABLED_NODE: &str = "(;-)";
     pub const DIRECTIVE: char = ':';
     pub const PARENTHESIS_OPEN: char = '(';
     pub const PARENTHESIS_CLOSE: char = ')';
@@ -23,9 +38,9 @@ mod token {
     pub const BRACE_CLOSE: char = '}';
 
     pub const PAIRS: &[Group] = &[
-        (PARENTHESIS_OPEN, PARENTHESIS_CLOSE, ItemKind::Parentheses),
-        (BRACKET_OPEN, BRACKET_CLOSE, ItemKind::Brackets),
-        (BRACE_OPEN, BRACE_CLOSE, ItemKind::Braces),
+        (PARENTHESIS_OPEN, PARENTHESIS_CLOSE, GroupKind::Parentheses),
+        (BRACKET_OPEN, BRACKET_CLOSE, GroupKind::Brackets),
+        (BRACE_OPEN, BRACE_CLOSE, GroupKind::Braces),
     ];
 
     pub const ALL: &[char] = &[
@@ -37,8 +52,14 @@ mod token {
 /// Type alias for [`Result`] with [`ParseError`].
 pub type ParseResult<T = ()> = Result<T, SourceError<ParseError>>;
 
-type GroupWrapper = fn(Vec<Item>) -> ItemKind;
-type Group = (char, char, GroupWrapper);
+type Group = (char, char, GroupKind);
+
+#[derive(Debug, Clone, Copy)]
+enum GroupKind {
+    Parentheses,
+    Brackets,
+    Braces,
+}
 
 /// Errors encountered during [`Tree::parse`].
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
@@ -59,15 +80,87 @@ pub enum ParseError {
     InvalidFloat { value: SmolStr },
     #[error("Empty directive signature")]
     EmptyDirectiveSignature,
+    #[error("Invalid pair key `{key}`")]
+    InvalidPairKey { key: SmolStr },
+    #[error("Dangling pair separator `{separator}`")]
+    DanglingSeparator { separator: char },
+    #[error("Repeated pair separator `{separator}`")]
+    RepeatedSeparator { separator: char },
+    #[error("Missing closing `;)` of block comment")]
+    UnclosedBlockComment,
+    #[error("Line is longer than {limit} bytes")]
+    LineLengthLimit { limit: usize },
+    #[error("Node is nested deeper than {limit} levels")]
+    DepthLimit { limit: usize },
+    #[error("Group is nested deeper than {limit} levels")]
+    GroupDepthLimit { limit: usize },
+    #[error("Node has more than {limit} items")]
+    ItemLimit { limit: usize },
+    #[error("Tree has more than {limit} nodes")]
+    NodeLimit { limit: usize },
 }
 
 pub(crate) fn parse_input(input: Input<'_>, indent: Indent) -> ParseResult<Tree> {
-    let mut stack = DepthStack::default();
+    parse_input_with(input, ParseOptions::new(indent))
+}
+
+pub(crate) fn parse_input_with(input: Input<'_>, options: ParseOptions) -> ParseResult<Tree> {
+    let roots = parse_roots(&mut Owned, input, options)?;
+    Ok(Tree { roots })
+}
+
+pub(crate) fn parse_borrowed(
+    input: Input<'_>,
+    options: ParseOptions,
+) -> ParseResult<borrowed::Tree<'_>> {
+    let roots = parse_roots(&mut Borrowed, input, options)?;
+    Ok(borrowed::Tree { roots })
+}
+
+pub(crate) fn parse_interned(
+    input: Input<'_>,
+    options: ParseOptions,
+    interner: &mut intern::Interner,
+) -> ParseResult<intern::Tree> {
+    let roots = parse_roots(&mut Interned(interner), input, options)?;
+    Ok(intern::Tree { roots })
+}
+
+fn parse_roots<'a, B>(
+    builder: &mut B,
+    input: Input<'a>,
+    options: ParseOptions,
+) -> ParseResult<Vec<B::Node>>
+where
+    B: Build<'a>,
+{
+    let mut limits = Limits::new(options);
+    let mut stack = DepthStack::<B>::new();
+    let mut docs = Vec::new();
     let mut input = Some(input);
     while let Some(current) = input.take() {
         let (line, rest) = current.split_line();
         input = rest;
 
+        limits.check_line(&line)?;
+
+        let marker = skip_indentation(&line);
+        if marker.content().starts_with(token::DISABLED_NODE) {
+            docs.clear();
+            input = skip_disabled_node(&limits, &line, input)?;
+            continue;
+        }
+
+        if marker.content().starts_with(token::BLOCK_COMMENT_OPEN) {
+            input = skip_block_comment(&limits, &marker, input)?;
+            continue;
+        }
+
+        if let Some((text, location)) = try_doc_comment(&line) {
+            docs.push(B::doc(text, location));
+            continue;
+        }
+
         if line.skip_whitespace_and_comments().is_empty() {
             continue;
         }
@@ -76,26 +169,34 @@ pub(crate) fn parse_input(input: Input<'_>, indent: Indent) -> ParseResult<Tree>
             break;
         }
 
-        let (depth, line) = indent.extract(line)?;
-        let node = parse_node(line)?;
+        let (depth, line) = options.indent.extract(line)?;
+        limits.check_node(depth, line.offset())?;
+        let comment = match options.trailing_comments {
+            true => try_trailing_comment(&line),
+            false => None,
+        };
+        let mut node = parse_node(builder, &mut limits, std::mem::take(&mut docs), line)?;
+        if let Some((text, location)) = comment {
+            B::comment(&mut node, text, location);
+        }
         stack.insert(depth, node)?;
     }
-    stack.into_tree()
+    stack.into_roots()
 }
 
-fn parse_node(mut input: Input<'_>) -> ParseResult<Node> {
+fn parse_node<'a, B: Build<'a>>(
+    builder: &mut B,
+    limits: &mut Limits,
+    docs: Vec<B::Doc>,
+    mut input: Input<'a>,
+) -> ParseResult<B::Node> {
     let node_offset = input.offset();
     let mut items = Vec::new();
     'items: loop {
         input = input.skip_whitespace_and_comments();
         return {
             if input.is_empty() {
-                Ok(Node {
-                    location: node_offset,
-                    kind: NodeKind::Statement(Statement {
-                        signature: items,
-                    }),
-                })
+                Ok(B::statement(docs, items, node_offset))
             } else if let Some(rest) = input.skip_char(':') {
                 if items.is_empty() {
                     Err(SourceError::new(
@@ -104,18 +205,11 @@ fn parse_node(mut input: Input<'_>) -> ParseResult<Node> {
                         "empty directive",
                     ))
                 } else {
-                    let arguments = parse_all_items(rest)?;
-                    Ok(Node {
-                        location: node_offset,
-                        kind: NodeKind::Directive(Directive {
-                            signature: items,
-                            arguments,
-                            children: Vec::new(),
-                        }),
-                    })
+                    let arguments = parse_all_items(builder, limits, rest)?;
+                    Ok(B::directive(docs, items, arguments, node_offset))
                 }
             } else {
-                let (item, rest) = parse_item(input)?;
+                let (item, rest) = parse_item(builder, limits, input)?;
                 items.push(item);
                 input = rest;
                 continue 'items;
@@ -124,25 +218,19 @@ fn parse_node(mut input: Input<'_>) -> ParseResult<Node> {
     }
 }
 
-fn parse_items_until(
-    mut input: Input<'_>,
-    end: char,
-    open_offset: Offset,
-) -> ParseResult<(Vec<Item>, Input<'_>)> {
+fn parse_all_items<'a, B: Build<'a>>(
+    builder: &mut B,
+    limits: &mut Limits,
+    mut input: Input<'a>,
+) -> ParseResult<Vec<B::Item>> {
     let mut items = Vec::new();
     'items: loop {
         input = input.skip_whitespace_and_comments();
         return {
             if input.is_empty() {
-                Err(SourceError::new(
-                    ParseError::UnclosedGroup { missing: end },
-                    open_offset,
-                    "opened here",
-                ))
-            } else if let Some(rest) = input.skip_char(end) {
-                Ok((items, rest))
+                Ok(items)
             } else {
-                let (item, rest) = parse_item(input)?;
+                let (item, rest) = parse_item(builder, limits, input)?;
                 items.push(item);
                 input = rest;
                 continue 'items;
@@ -151,53 +239,112 @@ fn parse_items_until(
     }
 }
 
-fn parse_all_items(mut input: Input<'_>) -> ParseResult<Vec<Item>> {
-    let mut items = Vec::new();
-    'items: loop {
-        input = input.skip_whitespace_and_comments();
-        return {
-            if input.is_empty() {
-                Ok(items)
-            } else {
-                let (item, rest) = parse_item(input)?;
-                items.push(item);
-                input = rest;
-                continue 'items;
+/// A group whose closing character has not been reached yet.
+struct OpenGroup<I> {
+    close: char,
+    kind: GroupKind,
+    location: Span,
+    items: Vec<I>,
+}
+
+/// A pair whose value is a group that has not been closed yet.
+struct OpenPair<I> {
+    key: I,
+    separator: char,
+    location: Span,
+    /// The number of open groups containing the pair.
+    depth: usize,
+}
+
+/// Parse a single item, including all items nested inside of it.
+///
+/// Open groups are kept on an explicit stack, so deeply nested input cannot overflow the call
+/// stack.
+fn parse_item<'a, B: Build<'a>>(
+    builder: &mut B,
+    limits: &mut Limits,
+    mut input: Input<'a>,
+) -> ParseResult<(B::Item, Input<'a>)> {
+    let mut groups: Vec<OpenGroup<B::Item>> = Vec::new();
+    let mut pairs: Vec<OpenPair<B::Item>> = Vec::new();
+    loop {
+        let closed = match groups.last() {
+            Some(group) => {
+                input = input.skip_whitespace_and_comments();
+                if input.is_empty() {
+                    return Err(SourceError::new(
+                        ParseError::UnclosedGroup { missing: group.close },
+                        group.location.start(),
+                        "opened here",
+                    ));
+                }
+                input.skip_char(group.close)
+            },
+            None => None,
+        };
+        let (item, rest) = if let Some(rest) = closed {
+            let group = groups.pop().unwrap();
+            (B::group(group.kind, group.items, group.location), rest)
+        } else {
+            match parse_item_start(builder, limits, input, groups.len())? {
+                ItemStart::Item(item, rest) => (item, rest),
+                ItemStart::Group(group, rest) => {
+                    groups.push(group);
+                    input = rest;
+                    continue;
+                },
+                ItemStart::Pair(pair, rest) => {
+                    pairs.push(pair);
+                    input = rest;
+                    continue;
+                },
             }
         };
+        input = rest;
+        let item = match pairs.last() {
+            Some(pair) if pair.depth == groups.len() => {
+                let pair = pairs.pop().unwrap();
+                B::pair(pair.key, pair.separator, item, pair.location)
+            },
+            _ => item,
+        };
+        match groups.last_mut() {
+            Some(parent) => parent.items.push(item),
+            None => return Ok((item, input)),
+        }
     }
 }
 
-fn parse_item(input: Input<'_>) -> ParseResult<(Item, Input<'_>)> {
-    if let Some((rest, (_, close, wrap_kind))) = try_skip_group_open(&input) {
-        let (items, rest) = parse_items_until(rest, close, input.offset())?;
-        let location = input.offset().span(input.skip(1).offset());
-        Ok((Item { location, kind: wrap_kind(items) }, rest))
+enum ItemStart<'a, I> {
+    Item(I, Input<'a>),
+    Group(OpenGroup<I>, Input<'a>),
+    /// A pair followed by the group that is its value.
+    Pair(OpenPair<I>, Input<'a>),
+}
+
+/// Parse a word, number or pair, or the opening character of a group nested in `depth` groups.
+fn parse_item_start<'a, B: Build<'a>>(
+    builder: &mut B,
+    limits: &mut Limits,
+    input: Input<'a>,
+    depth: usize,
+) -> ParseResult<ItemStart<'a, B::Item>> {
+    limits.check_item(input.offset())?;
+    if let Some((rest, (_, close, kind))) = try_skip_group_open(&input) {
+        limits.check_group_depth(depth + 1, input.offset())?;
+        let location = input.offset().span(rest.offset());
+        Ok(ItemStart::Group(OpenGroup { close, kind, location, items: Vec::new() }, rest))
     } else if let Some((value, span, rest)) = input.try_take_chars(|c| !is_structure_char(c)) {
-        if value.starts_with(|c: char| c.is_ascii_digit()) || value.starts_with('-') {
-            if value.contains('.') {
-                if let Some(value) = value.parse().ok() {
-                    Ok((Item { location: span, kind: ItemKind::Float(value) }, rest))
-                } else {
-                    Err(SourceError::new(
-                        ParseError::InvalidFloat { value: value.into() },
-                        span.start(),
-                        "expected valid float",
-                    ))
-                }
-            } else {
-                if let Some(value) = value.parse().ok() {
-                    Ok((Item { location: span, kind: ItemKind::Int(value) }, rest))
-                } else {
-                    Err(SourceError::new(
-                        ParseError::InvalidInt { value: value.into() },
-                        span.start(),
-                        "expected valid int",
-                    ))
-                }
-            }
-        } else {
-            Ok((Item { location: span, kind: ItemKind::Word(value.into()) }, rest))
+        // Numbers never form pairs, so `1=2` is an invalid number instead of a pair.
+        let separator = limits.options.pair_separator
+            .filter(|_| !starts_number(value))
+            .and_then(|separator| Some((separator, value.find(separator)?)));
+        let options = &limits.options;
+        match separator {
+            Some((separator, index)) => {
+                parse_pair_start(builder, options, separator, index, input, rest, depth)
+            },
+            None => Ok(ItemStart::Item(parse_scalar(builder, options, value, span)?, rest)),
         }
     } else {
         Err(SourceError::new(
@@ -208,6 +355,201 @@ fn parse_item(input: Input<'_>) -> ParseResult<(Item, Input<'_>)> {
     }
 }
 
+fn starts_number(value: &str) -> bool {
+    value.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+}
+
+/// Parse a number, literal, sigil or word.
+fn parse_scalar<'a, B: Build<'a>>(
+    builder: &mut B,
+    options: &ParseOptions,
+    value: &'a str,
+    span: Span,
+) -> ParseResult<B::Item> {
+    if starts_number(value) {
+        if value.contains('.') {
+            if let Ok(value) = value.parse() {
+                Ok(B::float(value, span))
+            } else {
+                Err(SourceError::new(
+                    ParseError::InvalidFloat { value: value.into() },
+                    span.start(),
+                    "expected valid float",
+                ))
+            }
+        } else {
+            if let Ok(value) = value.parse() {
+                Ok(B::int(value, span))
+            } else {
+                Err(SourceError::new(
+                    ParseError::InvalidInt { value: value.into() },
+                    span.start(),
+                    "expected valid int",
+                ))
+            }
+        }
+    } else if let Some(literal) = options.literals.and_then(|literals| literals.get(value)) {
+        Ok(match literal {
+            Literal::Bool(value) => B::bool(value, span),
+            Literal::Null => B::null(span),
+        })
+    } else if let Some((sigil, name)) = split_sigil(value, options.sigils) {
+        Ok(builder.sigil(sigil, name, span))
+    } else {
+        Ok(builder.word(value, span))
+    }
+}
+
+/// Split a word into one of the `sigils` and a non-empty name.
+fn split_sigil<'a>(value: &'a str, sigils: &[char]) -> Option<(char, &'a str)> {
+    let sigil = value.chars().next().filter(|sigil| sigils.contains(sigil))?;
+    let name = &value[sigil.len_utf8()..];
+    (!name.is_empty()).then_some((sigil, name))
+}
+
+/// Parse a pair nested in `depth` groups from the word at `input` with a separator at byte
+/// `index`, where `rest` follows the word.
+///
+/// The value is the remainder of the word after the first separator, or the group directly
+/// following the word if there is no remainder.
+fn parse_pair_start<'a, B: Build<'a>>(
+    builder: &mut B,
+    options: &ParseOptions,
+    separator: char,
+    index: usize,
+    input: Input<'a>,
+    rest: Input<'a>,
+    depth: usize,
+) -> ParseResult<ItemStart<'a, B::Item>> {
+    let separator_start = input.skip(index);
+    let separator_end = separator_start.skip(separator.len_utf8());
+    if index == 0 {
+        return Err(SourceError::new(
+            ParseError::DanglingSeparator { separator },
+            separator_start.offset(),
+            "missing key",
+        ));
+    }
+    let key_text = &input.content()[..index];
+    if !is_valid_word(key_text) {
+        return Err(SourceError::new(
+            ParseError::InvalidPairKey { key: key_text.into() },
+            input.offset(),
+            "expected word key",
+        ));
+    }
+    let key_location = input.offset().span(separator_start.offset());
+    let key = builder.word(key_text, key_location);
+    let location = input.offset().span(separator_end.offset());
+
+    let value_len = separator_end.content().len() - rest.content().len();
+    if let Some(repeated) = separator_end.content()[..value_len].find(separator) {
+        return Err(SourceError::new(
+            ParseError::RepeatedSeparator { separator },
+            separator_end.skip(repeated).offset(),
+            "repeated separator",
+        ));
+    }
+    if value_len > 0 {
+        let value_location = separator_end.offset().span(rest.offset());
+        let value_text = &separator_end.content()[..value_len];
+        let value = parse_scalar(builder, options, value_text, value_location)?;
+        Ok(ItemStart::Item(B::pair(key, separator, value, location), rest))
+    } else if try_skip_group_open(&rest).is_some() {
+        Ok(ItemStart::Pair(OpenPair { key, separator, location, depth }, rest))
+    } else {
+        Err(SourceError::new(
+            ParseError::DanglingSeparator { separator },
+            separator_start.offset(),
+            "missing value",
+        ))
+    }
+}
+
+/// The text and location of a line containing only a doc comment.
+pub(crate) fn try_doc_comment<'a>(line: &Input<'a>) -> Option<(&'a str, Span)> {
+    let comment = skip_indentation(line);
+    if !comment.content().starts_with(token::DOC_COMMENT) {
+        return None;
+    }
+    Some(comment_text(&comment, token::DOC_COMMENT.len()))
+}
+
+/// The text and location of a comment at the end of a node line.
+fn try_trailing_comment<'a>(line: &Input<'a>) -> Option<(&'a str, Span)> {
+    let index = line.content().find(token::COMMENT)?;
+    Some(comment_text(&line.skip(index), token::COMMENT.len_utf8()))
+}
+
+/// The text following the marker of a comment and a single space, and the location of the
+/// whole comment.
+fn comment_text<'a>(comment: &Input<'a>, marker_len: usize) -> (&'a str, Span) {
+    let text = comment.skip(marker_len);
+    let text = text.skip_char(' ').unwrap_or(text);
+    let location = comment.offset().span(comment.end().offset());
+    (text.content().trim_end(), location)
+}
+
+/// The input following a block comment opened at `open`, which includes all lines up to the
+/// first one ending in the closing marker.
+fn skip_block_comment<'a>(
+    limits: &Limits,
+    open: &Input<'a>,
+    mut rest: Option<Input<'a>>,
+) -> ParseResult<Option<Input<'a>>> {
+    let mut line = open.skip(token::BLOCK_COMMENT_OPEN.len());
+    loop {
+        if line.content().trim_end().ends_with(token::BLOCK_COMMENT_CLOSE) {
+            return Ok(rest);
+        }
+        let Some(current) = rest else {
+            return Err(SourceError::new(
+                ParseError::UnclosedBlockComment,
+                open.offset(),
+                "opened here",
+            ));
+        };
+        let (next, next_rest) = current.split_line();
+        limits.check_line(&next)?;
+        line = next;
+        rest = next_rest;
+    }
+}
+
+/// The input following a disabled node on `line` and all lines indented deeper than it.
+///
+/// Indentation is only compared by length, since it is not validated for disabled nodes.
+/// Blank and comment-only lines are skipped with the node only if a deeper line follows them.
+fn skip_disabled_node<'a>(
+    limits: &Limits,
+    line: &Input<'a>,
+    mut rest: Option<Input<'a>>,
+) -> ParseResult<Option<Input<'a>>> {
+    let indentation = indentation_len(line);
+    let mut skipped = rest.clone();
+    while let Some(current) = rest {
+        let (line, next) = current.split_line();
+        limits.check_line(&line)?;
+        if !line.skip_whitespace_and_comments().is_empty() {
+            if indentation_len(&line) <= indentation {
+                break;
+            }
+            skipped = next.clone();
+        }
+        rest = next;
+    }
+    Ok(skipped)
+}
+
+fn skip_indentation<'a>(line: &Input<'a>) -> Input<'a> {
+    line.skip(indentation_len(line))
+}
+
+fn indentation_len(line: &Input<'_>) -> usize {
+    let content = line.content();
+    content.len() - content.trim_start().len()
+}
+
 fn try_skip_group_open<'a>(input: &Input<'a>) -> Option<(Input<'a>, Group)> {
     for &group in token::PAIRS {
         let (open, ..) = group;
@@ -218,32 +560,98 @@ fn try_skip_group_open<'a>(input: &Input<'a>) -> Option<(Input<'a>, Group)> {
     None
 }
 
-fn is_structure_char(c: char) -> bool {
+pub(crate) fn is_structure_char(c: char) -> bool {
     c.is_whitespace() || token::ALL.contains(&c)
 }
 
-#[derive(Default)]
-struct DepthStack {
-    tree: Tree,
-    levels: Vec<Node>,
+/// Whether `c` cannot be a sigil or pair separator.
+const fn is_reserved_char(c: char) -> bool {
+    if c.is_whitespace() || c.is_ascii_digit() || c == '-' {
+        return true;
+    }
+    let mut index = 0;
+    while index < token::ALL.len() {
+        if token::ALL[index] == c {
+            return true;
+        }
+        index += 1;
+    }
+    false
+}
+
+/// Check that a string parses back as a single word.
+pub(crate) fn is_valid_word(value: &str) -> bool {
+    !value.is_empty()
+        && value != "__END__"
+        && !starts_number(value)
+        && !value.contains(is_structure_char)
+}
+
+/// Check that a word parses back as a word item with `options`, instead of as a literal,
+/// sigil or pair.
+pub(crate) fn parses_as_word(value: &str, options: &ParseOptions) -> bool {
+    is_valid_word(value) && matches!(word_kind(value, options), Some(ItemKind::Word(_)))
+}
+
+/// The kind of item a valid word is parsed as with `options`, or `None` if it starts a pair.
+pub(crate) fn word_kind(value: &str, options: &ParseOptions) -> Option<ItemKind> {
+    if options.pair_separator.is_some_and(|separator| value.contains(separator)) {
+        return None;
+    }
+    Some(match options.literals.and_then(|literals| literals.get(value)) {
+        Some(Literal::Bool(value)) => ItemKind::Bool(value),
+        Some(Literal::Null) => ItemKind::Null,
+        None => match split_sigil(value, options.sigils) {
+            Some((sigil, name)) => ItemKind::Sigil { sigil, name: name.into() },
+            None => ItemKind::Word(value.into()),
+        },
+    })
+}
+
+#[cfg(feature = "json")]
+/// A word parsed as the boolean `value`, or as null for `None`, with `options`.
+pub(crate) fn literal_word(value: Option<bool>, options: &ParseOptions) -> Option<&'static str> {
+    let literals = options.literals?;
+    let words = match value {
+        Some(true) => literals.true_words,
+        Some(false) => literals.false_words,
+        None => literals.null_words,
+    };
+    words.iter().copied().find(|word| is_valid_word(word))
 }
 
-impl DepthStack {
-    fn into_tree(mut self) -> ParseResult<Tree> {
+/// Check that a key and separator parse back as the start of a pair.
+pub(crate) fn is_valid_pair_key(key: &str, separator: char) -> bool {
+    is_valid_word(key)
+        && !key.contains(separator)
+        && !is_structure_char(separator)
+}
+
+struct DepthStack<'a, B: Build<'a>> {
+    roots: Vec<B::Node>,
+    levels: Vec<B::Node>,
+}
+
+impl<'a, B: Build<'a>> DepthStack<'a, B> {
+    fn new() -> Self {
+        Self { roots: Vec::new(), levels: Vec::new() }
+    }
+
+    fn into_roots(mut self) -> ParseResult<Vec<B::Node>> {
         self.vacate_level(0)?;
-        Ok(self.tree)
+        Ok(self.roots)
     }
 
-    fn insert(&mut self, depth: usize, node: Node) -> ParseResult {
+    fn insert(&mut self, depth: usize, node: B::Node) -> ParseResult {
         self.vacate_level(depth)?;
         if depth != self.levels.len() {
             let mut error = SourceError::new(
                 ParseError::IndentDepth,
-                node.location,
+                B::location(&node),
                 "invalid indentation",
             );
             if let Some(nearest) = self.levels.last() {
-                error = error.with_context(nearest.location);
+                error = error.with_context(B::location(nearest));
             }
             return Err(error);
         }
@@ -255,22 +663,17 @@ impl DepthStack {
         while self.levels.len() > depth {
             let node = self.levels.pop().unwrap();
             if let Some(parent) = self.levels.last_mut() {
-                match &mut parent.kind {
-                    NodeKind::Directive(Directive { children, .. }) => {
-                        children.push(node);
-                    },
-                    NodeKind::Statement(_) => {
-                        let error = SourceError::new(
-                            ParseError::StatementWithChild,
-                            node.location,
-                            "child node",
-                        );
-                        let error = error.with_context(parent.location);
-                        return Err(error);
-                    },
+                if let Err(node) = B::push_child(parent, node) {
+                    let error = SourceError::new(
+                        ParseError::StatementWithChild,
+                        B::location(&node),
+                        "child node",
+                    );
+                    let error = error.with_context(B::location(parent));
+                    return Err(error);
                 }
             } else {
-                self.tree.roots.push(node);
+                self.roots.push(node);
             }
         }
         Ok(())
@@ -311,6 +714,272 @@ impl Indent {
     }
 }
 
+/// Settings for [`Tree::parse_with`].
+///
+/// Literals, sigils and pairs are not parsed, trailing comments are not recorded and all limits
+/// are disabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    indent: Indent,
+    literals: Option<Literals>,
+    sigils: &'static [char],
+    pair_separator: Option<char>,
+    trailing_comments: bool,
+    max_line_length: Option<usize>,
+    max_depth: Option<usize>,
+    max_group_depth: Option<usize>,
+    max_items: Option<usize>,
+    max_nodes: Option<usize>,
+}
+
+impl ParseOptions {
+    /// Options using the given [`Indent`] without any limits.
+    pub const fn new(indent: Indent) -> Self {
+        Self {
+            indent,
+            literals: None,
+            sigils: &[],
+            pair_separator: None,
+            trailing_comments: false,
+            max_line_length: None,
+            max_depth: None,
+            max_group_depth: None,
+            max_items: None,
+            max_nodes: None,
+        }
+    }
+
+    /// Parse the words of `literals` as [`ItemKind::Bool`](crate::ItemKind::Bool) and
+    /// [`ItemKind::Null`](crate::ItemKind::Null) items.
+    pub const fn literals(mut self, literals: Literals) -> Self {
+        self.literals = Some(literals);
+        self
+    }
+
+    /// Parse words starting with one of `sigils` as [`ItemKind::Sigil`](crate::ItemKind::Sigil)
+    /// items.
+    ///
+    /// Words consisting of only a sigil stay words.
+    ///
+    /// Returns `None` if a sigil is whitespace, a structural character like `:` or a character
+    /// starting numbers.
+    pub const fn try_sigils(mut self, sigils: &'static [char]) -> Option<Self> {
+        let mut index = 0;
+        while index < sigils.len() {
+            if is_reserved_char(sigils[index]) {
+                return None;
+            }
+            index += 1;
+        }
+        self.sigils = sigils;
+        Some(self)
+    }
+
+    /// Parse words starting with one of `sigils` as [`ItemKind::Sigil`](crate::ItemKind::Sigil)
+    /// items.
+    ///
+    /// Words consisting of only a sigil stay words.
+    ///
+    /// Panics if a sigil is whitespace, a structural character like `:` or a character starting
+    /// numbers.
+    pub const fn sigils(self, sigils: &'static [char]) -> Self {
+        match self.try_sigils(sigils) {
+            Some(options) => options,
+            None => panic!("invalid sigil specified"),
+        }
+    }
+
+    /// Parse words containing `separator` as [`ItemKind::Pair`](crate::ItemKind::Pair) items.
+    ///
+    /// Returns `None` if the separator is whitespace, a structural character like `:` or a
+    /// character starting numbers.
+    pub const fn try_pair_separator(mut self, separator: char) -> Option<Self> {
+        if is_reserved_char(separator) {
+            return None;
+        }
+        self.pair_separator = Some(separator);
+        Some(self)
+    }
+
+    /// Parse words containing `separator` as [`ItemKind::Pair`](crate::ItemKind::Pair) items.
+    ///
+    /// Panics if the separator is whitespace, a structural character like `:` or a character
+    /// starting numbers.
+    pub const fn pair_separator(self, separator: char) -> Self {
+        match self.try_pair_separator(separator) {
+            Some(options) => options,
+            None => panic!("invalid pair separator specified"),
+        }
+    }
+
+    /// Record the comment at the end of each node line as [`Node::comment`](crate::Node::comment).
+    pub const fn trailing_comments(mut self, enabled: bool) -> Self {
+        self.trailing_comments = enabled;
+        self
+    }
+
+    /// Limit the length of lines in bytes, including comments.
+    pub const fn max_line_length(mut self, limit: usize) -> Self {
+        self.max_line_length = Some(limit);
+        self
+    }
+
+    /// Limit the indentation depth of nodes. Root nodes have a depth of 0.
+    pub const fn max_depth(mut self, limit: usize) -> Self {
+        self.max_depth = Some(limit);
+        self
+    }
+
+    /// Limit the number of groups nested inside each other.
+    pub const fn max_group_depth(mut self, limit: usize) -> Self {
+        self.max_group_depth = Some(limit);
+        self
+    }
+
+    /// Limit the number of items in a node, counting items inside of groups.
+    pub const fn max_items(mut self, limit: usize) -> Self {
+        self.max_items = Some(limit);
+        self
+    }
+
+    /// Limit the total number of nodes.
+    pub const fn max_nodes(mut self, limit: usize) -> Self {
+        self.max_nodes = Some(limit);
+        self
+    }
+
+    pub(crate) const fn indent(&self) -> Indent {
+        self.indent
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) const fn records_comments(&self) -> bool {
+        self.trailing_comments
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) const fn separator(&self) -> Option<char> {
+        self.pair_separator
+    }
+}
+
+impl From<Indent> for ParseOptions {
+    fn from(indent: Indent) -> Self {
+        Self::new(indent)
+    }
+}
+
+/// The words parsed as boolean and null literals with [`ParseOptions::literals`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Literals {
+    true_words: &'static [&'static str],
+    false_words: &'static [&'static str],
+    null_words: &'static [&'static str],
+}
+
+impl Literals {
+    /// The words `true`, `false` and `null`.
+    pub const DEFAULT: Self = Self::new(&["true"], &["false"], &["null"]);
+
+    /// Literals with the given words for each value.
+    pub const fn new(
+        true_words: &'static [&'static str],
+        false_words: &'static [&'static str],
+        null_words: &'static [&'static str],
+    ) -> Self {
+        Self { true_words, false_words, null_words }
+    }
+
+    fn get(&self, word: &str) -> Option<Literal> {
+        if self.true_words.contains(&word) {
+            Some(Literal::Bool(true))
+        } else if self.false_words.contains(&word) {
+            Some(Literal::Bool(false))
+        } else if self.null_words.contains(&word) {
+            Some(Literal::Null)
+        } else {
+            None
+        }
+    }
+}
+
+enum Literal {
+    Bool(bool),
+    Null,
+}
+
+impl Default for Literals {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Counts checked against the limits in [`ParseOptions`].
+struct Limits {
+    options: ParseOptions,
+    nodes: usize,
+    items: usize,
+}
+
+impl Limits {
+    fn new(options: ParseOptions) -> Self {
+        Self { options, nodes: 0, items: 0 }
+    }
+
+    fn check_line(&self, line: &Input<'_>) -> ParseResult {
+        match self.options.max_line_length {
+            Some(limit) if line.content().len() > limit => Err(SourceError::new(
+                ParseError::LineLengthLimit { limit },
+                line.offset(),
+                "line too long",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count a new node at `depth` and reset the item count.
+    fn check_node(&mut self, depth: usize, offset: Offset) -> ParseResult {
+        self.nodes += 1;
+        self.items = 0;
+        match (self.options.max_depth, self.options.max_nodes) {
+            (Some(limit), _) if depth > limit => Err(SourceError::new(
+                ParseError::DepthLimit { limit },
+                offset,
+                "nested too deeply",
+            )),
+            (_, Some(limit)) if self.nodes > limit => Err(SourceError::new(
+                ParseError::NodeLimit { limit },
+                offset,
+                "too many nodes",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_item(&mut self, offset: Offset) -> ParseResult {
+        self.items += 1;
+        match self.options.max_items {
+            Some(limit) if self.items > limit => Err(SourceError::new(
+                ParseError::ItemLimit { limit },
+                offset,
+                "too many items",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_group_depth(&self, depth: usize, offset: Offset) -> ParseResult {
+        match self.options.max_group_depth {
+            Some(limit) if depth > limit => Err(SourceError::new(
+                ParseError::GroupDepthLimit { limit },
+                offset,
+                "nested too deeply",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum IndentWidth {
     Tabs,
@@ -318,6 +987,20 @@ enum IndentWidth {
 }
 
 impl Indent {
+    pub(crate) fn write_prefix(&self, out: &mut String, depth: usize) {
+        use IndentWidth::*;
+        for _ in 0..depth {
+            match self.width {
+                Tabs => out.push('\t'),
+                Spaces(n) => {
+                    for _ in 0..n {
+                        out.push(' ');
+                    }
+                },
+            }
+        }
+    }
+
     fn try_deindent<'a>(&self, line: Input<'a>) -> Option<Input<'a>> {
         use IndentWidth::*;
         match self.width {
@@ -0,0 +1,307 @@
+use src_ctx::{Offset, Span};
+
+use crate::{Node, NodeKind, Statement, Directive, Item, ItemKind, Doc, Comment, borrowed, intern};
+use super::GroupKind;
+
+
+/// Construction of the nodes and items of a tree representation during parsing.
+///
+/// Words are built through the builder value, which can hold state such as an interner.
+pub(super) trait Build<'a> {
+    type Node;
+    type Item;
+    type Doc;
+
+    fn word(&mut self, value: &'a str, location: Span) -> Self::Item;
+
+    fn sigil(&mut self, sigil: char, name: &'a str, location: Span) -> Self::Item;
+
+    fn int(value: i32, location: Span) -> Self::Item;
+
+    fn float(value: f32, location: Span) -> Self::Item;
+
+    fn bool(value: bool, location: Span) -> Self::Item;
+
+    fn null(location: Span) -> Self::Item;
+
+    fn group(kind: GroupKind, items: Vec<Self::Item>, location: Span) -> Self::Item;
+
+    fn pair(key: Self::Item, separator: char, value: Self::Item, location: Span) -> Self::Item;
+
+    fn doc(text: &'a str, location: Span) -> Self::Doc;
+
+    fn statement(docs: Vec<Self::Doc>, signature: Vec<Self::Item>, location: Offset) -> Self::Node;
+
+    fn directive(
+        docs: Vec<Self::Doc>,
+        signature: Vec<Self::Item>,
+        arguments: Vec<Self::Item>,
+        location: Offset,
+    ) -> Self::Node;
+
+    /// Record the trailing comment of a node.
+    fn comment(node: &mut Self::Node, text: &'a str, location: Span);
+
+    fn location(node: &Self::Node) -> Offset;
+
+    /// Returns the child if the parent cannot have children.
+    fn push_child(parent: &mut Self::Node, child: Self::Node) -> Result<(), Self::Node>;
+}
+
+/// Builds a [`Tree`](crate::Tree).
+pub(super) struct Owned;
+
+impl<'a> Build<'a> for Owned {
+    type Node = Node;
+    type Item = Item;
+    type Doc = Doc;
+
+    fn word(&mut self, value: &'a str, location: Span) -> Item {
+        Item { kind: ItemKind::Word(value.into()), location }
+    }
+
+    fn sigil(&mut self, sigil: char, name: &'a str, location: Span) -> Item {
+        Item { kind: ItemKind::Sigil { sigil, name: name.into() }, location }
+    }
+
+    fn int(value: i32, location: Span) -> Item {
+        Item { kind: ItemKind::Int(value), location }
+    }
+
+    fn float(value: f32, location: Span) -> Item {
+        Item { kind: ItemKind::Float(value), location }
+    }
+
+    fn bool(value: bool, location: Span) -> Item {
+        Item { kind: ItemKind::Bool(value), location }
+    }
+
+    fn null(location: Span) -> Item {
+        Item { kind: ItemKind::Null, location }
+    }
+
+    fn group(kind: GroupKind, items: Vec<Item>, location: Span) -> Item {
+        let kind = match kind {
+            GroupKind::Parentheses => ItemKind::Parentheses(items),
+            GroupKind::Brackets => ItemKind::Brackets(items),
+            GroupKind::Braces => ItemKind::Braces(items),
+        };
+        Item { kind, location }
+    }
+
+    fn pair(key: Item, separator: char, value: Item, location: Span) -> Item {
+        let kind = ItemKind::Pair { key: Box::new(key), separator, value: Box::new(value) };
+        Item { kind, location }
+    }
+
+    fn doc(text: &'a str, location: Span) -> Doc {
+        Doc { text: text.into(), location }
+    }
+
+    fn statement(docs: Vec<Doc>, signature: Vec<Item>, location: Offset) -> Node {
+        Node { kind: NodeKind::Statement(Statement { signature }), location, docs, comment: None }
+    }
+
+    fn directive(
+        docs: Vec<Doc>,
+        signature: Vec<Item>,
+        arguments: Vec<Item>,
+        location: Offset,
+    ) -> Node {
+        let directive = Directive { signature, arguments, children: Vec::new() };
+        Node { kind: NodeKind::Directive(directive), location, docs, comment: None }
+    }
+
+    fn comment(node: &mut Node, text: &'a str, location: Span) {
+        node.comment = Some(Comment { text: text.into(), location });
+    }
+
+    fn location(node: &Node) -> Offset {
+        node.location
+    }
+
+    fn push_child(parent: &mut Node, child: Node) -> Result<(), Node> {
+        match &mut parent.kind {
+            NodeKind::Directive(directive) => {
+                directive.children.push(child);
+                Ok(())
+            },
+            NodeKind::Statement(_) => Err(child),
+        }
+    }
+}
+
+/// Builds a [`borrowed::Tree`].
+pub(super) struct Borrowed;
+
+impl<'a> Build<'a> for Borrowed {
+    type Node = borrowed::Node<'a>;
+    type Item = borrowed::Item<'a>;
+    type Doc = borrowed::Doc<'a>;
+
+    fn word(&mut self, value: &'a str, location: Span) -> Self::Item {
+        borrowed::Item { kind: borrowed::ItemKind::Word(value), location }
+    }
+
+    fn sigil(&mut self, sigil: char, name: &'a str, location: Span) -> Self::Item {
+        borrowed::Item { kind: borrowed::ItemKind::Sigil { sigil, name }, location }
+    }
+
+    fn int(value: i32, location: Span) -> Self::Item {
+        borrowed::Item { kind: borrowed::ItemKind::Int(value), location }
+    }
+
+    fn float(value: f32, location: Span) -> Self::Item {
+        borrowed::Item { kind: borrowed::ItemKind::Float(value), location }
+    }
+
+    fn bool(value: bool, location: Span) -> Self::Item {
+        borrowed::Item { kind: borrowed::ItemKind::Bool(value), location }
+    }
+
+    fn null(location: Span) -> Self::Item {
+        borrowed::Item { kind: borrowed::ItemKind::Null, location }
+    }
+
+    fn group(kind: GroupKind, items: Vec<Self::Item>, location: Span) -> Self::Item {
+        let kind = match kind {
+            GroupKind::Parentheses => borrowed::ItemKind::Parentheses(items),
+            GroupKind::Brackets => borrowed::ItemKind::Brackets(items),
+            GroupKind::Braces => borrowed::ItemKind::Braces(items),
+        };
+        borrowed::Item { kind, location }
+    }
+
+    fn pair(key: Self::Item, separator: char, value: Self::Item, location: Span) -> Self::Item {
+        let (key, value) = (Box::new(key), Box::new(value));
+        borrowed::Item { kind: borrowed::ItemKind::Pair { key, separator, value }, location }
+    }
+
+    fn doc(text: &'a str, location: Span) -> Self::Doc {
+        borrowed::Doc { text, location }
+    }
+
+    fn statement(
+        docs: Vec<Self::Doc>,
+        signature: Vec<Self::Item>,
+        location: Offset,
+    ) -> Self::Node {
+        let kind = borrowed::NodeKind::Statement(borrowed::Statement { signature });
+        borrowed::Node { kind, location, docs, comment: None }
+    }
+
+    fn directive(
+        docs: Vec<Self::Doc>,
+        signature: Vec<Self::Item>,
+        arguments: Vec<Self::Item>,
+        location: Offset,
+    ) -> Self::Node {
+        let directive = borrowed::Directive { signature, arguments, children: Vec::new() };
+        let kind = borrowed::NodeKind::Directive(directive);
+        borrowed::Node { kind, location, docs, comment: None }
+    }
+
+    fn comment(node: &mut Self::Node, text: &'a str, location: Span) {
+        node.comment = Some(borrowed::Comment { text, location });
+    }
+
+    fn location(node: &Self::Node) -> Offset {
+        node.location
+    }
+
+    fn push_child(parent: &mut Self::Node, child: Self::Node) -> Result<(), Self::Node> {
+        match &mut parent.kind {
+            borrowed::NodeKind::Directive(directive) => {
+                directive.children.push(child);
+                Ok(())
+            },
+            borrowed::NodeKind::Statement(_) => Err(child),
+        }
+    }
+}
+
+/// Builds an [`intern::Tree`].
+pub(super) struct Interned<'i>(pub(super) &'i mut intern::Interner);
+
+impl<'a> Build<'a> for Interned<'_> {
+    type Node = intern::Node;
+    type Item = intern::Item;
+    type Doc = Doc;
+
+    fn word(&mut self, value: &'a str, location: Span) -> Self::Item {
+        intern::Item { kind: intern::ItemKind::Word(self.0.intern(value)), location }
+    }
+
+    fn sigil(&mut self, sigil: char, name: &'a str, location: Span) -> Self::Item {
+        let name = self.0.intern(name);
+        intern::Item { kind: intern::ItemKind::Sigil { sigil, name }, location }
+    }
+
+    fn int(value: i32, location: Span) -> Self::Item {
+        intern::Item { kind: intern::ItemKind::Int(value), location }
+    }
+
+    fn float(value: f32, location: Span) -> Self::Item {
+        intern::Item { kind: intern::ItemKind::Float(value), location }
+    }
+
+    fn bool(value: bool, location: Span) -> Self::Item {
+        intern::Item { kind: intern::ItemKind::Bool(value), location }
+    }
+
+    fn null(location: Span) -> Self::Item {
+        intern::Item { kind: intern::ItemKind::Null, location }
+    }
+
+    fn group(kind: GroupKind, items: Vec<Self::Item>, location: Span) -> Self::Item {
+        let kind = match kind {
+            GroupKind::Parentheses => intern::ItemKind::Parentheses(items),
+            GroupKind::Brackets => intern::ItemKind::Brackets(items),
+            GroupKind::Braces => intern::ItemKind::Braces(items),
+        };
+        intern::Item { kind, location }
+    }
+
+    fn pair(key: Self::Item, separator: char, value: Self::Item, location: Span) -> Self::Item {
+        let (key, value) = (Box::new(key), Box::new(value));
+        intern::Item { kind: intern::ItemKind::Pair { key, separator, value }, location }
+    }
+
+    fn doc(text: &'a str, location: Span) -> Doc {
+        Doc { text: text.into(), location }
+    }
+
+    fn statement(docs: Vec<Doc>, signature: Vec<Self::Item>, location: Offset) -> Self::Node {
+        let kind = intern::NodeKind::Statement(intern::Statement { signature });
+        intern::Node { kind, location, docs, comment: None }
+    }
+
+    fn directive(
+        docs: Vec<Doc>,
+        signature: Vec<Self::Item>,
+        arguments: Vec<Self::Item>,
+        location: Offset,
+    ) -> Self::Node {
+        let directive = intern::Directive { signature, arguments, children: Vec::new() };
+        let kind = intern::NodeKind::Directive(directive);
+        intern::Node { kind, location, docs, comment: None }
+    }
+
+    fn comment(node: &mut Self::Node, text: &'a str, location: Span) {
+        node.comment = Some(Comment { text: text.into(), location });
+    }
+
+    fn location(node: &Self::Node) -> Offset {
+        node.location
+    }
+
+    fn push_child(parent: &mut Self::Node, child: Self::Node) -> Result<(), Self::Node> {
+        match &mut parent.kind {
+            intern::NodeKind::Directive(directive) => {
+                directive.children.push(child);
+                Ok(())
+            },
+            intern::NodeKind::Statement(_) => Err(child),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::ops::Range;
+
+use smol_str::SmolStr;
+use src_ctx::{Input, Offset, Span};
+
+use crate::{Tree, Node, NodeKind, Item, ItemKind, Statement, Directive, Doc, Comment, Locator};
+
+use super::{ParseOptions, ParseResult, parse_input_with, try_doc_comment, token};
+use super::input::InputExt;
+
+
+/// A replacement of a byte range in a source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: SmolStr,
+}
+
+impl TextEdit {
+    /// Create an edit replacing the bytes in `range` with `replacement`.
+    pub fn new<R>(range: Range<usize>, replacement: R) -> Self
+    where
+        R: Into<SmolStr>,
+    {
+        Self { range, replacement: replacement.into() }
+    }
+
+    /// Apply the edit to `source`, producing the edited text.
+    ///
+    /// Panics if the range is out of bounds or not on character boundaries.
+    pub fn apply(&self, source: &str) -> String {
+        let mut edited = String::with_capacity(source.len() + self.replacement.len());
+        edited.push_str(&source[..self.range.start]);
+        edited.push_str(&self.replacement);
+        edited.push_str(&source[self.range.end..]);
+        edited
+    }
+
+    fn is_applicable(&self, source: &str) -> bool {
+        self.range.start <= self.range.end
+            && source.get(self.range.clone()).is_some()
+    }
+
+    fn is_applied(&self, old: &str, new: &str) -> bool {
+        let Range { start, end } = self.range;
+        let replaced_end = start + self.replacement.len();
+        new.len() + (end - start) == old.len() + self.replacement.len()
+            && new.get(..start) == old.get(..start)
+            && new.get(start..replaced_end) == Some(self.replacement.as_str())
+            && new.get(replaced_end..) == old.get(end..)
+    }
+}
+
+pub(crate) fn reparse_input(
+    tree: &Tree,
+    old: Input<'_>,
+    edit: &TextEdit,
+    new: Input<'_>,
+    options: ParseOptions,
+) -> ParseResult<Tree> {
+    try_reparse(tree, &old, edit, &new, options)
+        .unwrap_or_else(|| parse_input_with(new, options))
+}
+
+fn try_reparse(
+    tree: &Tree,
+    old: &Input<'_>,
+    edit: &TextEdit,
+    new: &Input<'_>,
+    options: ParseOptions,
+) -> Option<ParseResult<Tree>> {
+    // The node limit applies to the whole tree, not only to the reparsed region.
+    if options.max_nodes.is_some() {
+        return None;
+    }
+    if !edit.is_applicable(old.content()) || !edit.is_applied(old.content(), new.content()) {
+        return None;
+    }
+    if has_skipped_lines(old) || has_skipped_lines(new) {
+        return None;
+    }
+    if touches_docs(edit, old.content(), new.content()) {
+        return None;
+    }
+
+    let starts = root_line_starts(old);
+    if starts.len() != tree.roots.len() {
+        return None;
+    }
+
+    // The first affected root is the one preceding the edit start, since an edit at the
+    // very beginning of a line can turn it into a child of that root.
+    let first = starts.iter().rposition(|&start| start < edit.range.start).unwrap_or(0);
+    let last = starts.iter().rposition(|&start| start <= edit.range.end).unwrap_or(0);
+
+    let region_start = match first {
+        0 => 0,
+        _ => starts[first],
+    };
+    let region_end = starts.get(last + 1).copied().unwrap_or(old.content().len());
+    let new_region_end = region_end + edit.replacement.len() - edit.range.len();
+
+    let region = new.skip(region_start).truncate(new_region_end - region_start);
+    let region_tree = match parse_input_with(region, options) {
+        Ok(region_tree) => region_tree,
+        Err(_) => return None,
+    };
+
+    let mut roots = Vec::with_capacity(tree.roots.len() + region_tree.roots.len());
+
+    let mut head = Relocation::new(old, new, 0);
+    for root in &tree.roots[..first] {
+        roots.push(head.node(root)?);
+    }
+
+    roots.extend(region_tree.roots);
+
+    if last + 1 < starts.len() {
+        let shift = new_region_end as isize - region_end as isize;
+        let mut tail = Relocation::new(old, new, shift);
+        for root in &tree.roots[last + 1..] {
+            roots.push(tail.node(root)?);
+        }
+    }
+
+    Some(Ok(Tree { roots }))
+}
+
+/// Whether the input contains an end marker, block comments or disabled nodes, which cause
+/// following lines to be skipped instead of being parsed as nodes.
+///
+/// Disabled nodes are covered by the block comment check, since their marker starts the same.
+pub(super) fn has_skipped_lines(input: &Input<'_>) -> bool {
+    input.content().lines().map(str::trim).any(|line| {
+        line == "__END__"
+            || line.starts_with(token::BLOCK_COMMENT_OPEN)
+    })
+}
+
+/// The byte indices of the lines starting root nodes, or of their first doc comment line.
+pub(super) fn root_line_starts(input: &Input<'_>) -> Vec<usize> {
+    let len = input.content().len();
+    let mut starts = Vec::new();
+    let mut docs_start = None;
+    let mut rest = Some(input.clone());
+    while let Some(current) = rest.take() {
+        let (line, next) = current.split_line();
+        rest = next;
+
+        let start = len - current.content().len();
+        if try_doc_comment(&line).is_some() {
+            docs_start.get_or_insert(start);
+        } else if !line.skip_whitespace_and_comments().is_empty() {
+            let node_start = docs_start.take().unwrap_or(start);
+            if !line.content().starts_with(char::is_whitespace) {
+                starts.push(node_start);
+            }
+        }
+    }
+    starts
+}
+
+/// Whether the lines touched by the edit contain doc comments before or after the edit, which
+/// can change the docs of nodes outside of the reparsed region.
+fn touches_docs(edit: &TextEdit, old: &str, new: &str) -> bool {
+    let lines = |content: &str, start: usize, end: usize| {
+        let start = content[..start].rfind('\n').map_or(0, |index| index + 1);
+        let end = content[end..].find('\n').map_or(content.len(), |index| end + index);
+        content[start..end].contains(token::DOC_COMMENT)
+    };
+    lines(old, edit.range.start, edit.range.end)
+        || lines(new, edit.range.start, edit.range.start + edit.replacement.len())
+}
+
+/// Maps locations in reused nodes from the old source onto the new source.
+struct Relocation<'a, 'b> {
+    old: Locator<'a>,
+    new: Input<'b>,
+    shift: isize,
+}
+
+impl<'a, 'b> Relocation<'a, 'b> {
+    fn new(old: &Input<'a>, new: &Input<'b>, shift: isize) -> Self {
+        Self { old: Locator::new(old.clone()), new: new.clone(), shift }
+    }
+
+    fn node(&mut self, node: &Node) -> Option<Node> {
+        let docs = node.docs.iter()
+            .map(|doc| Some(Doc { text: doc.text.clone(), location: self.span(doc.location)? }))
+            .collect::<Option<_>>()?;
+        let location = self.offset(node.location)?;
+        let (signature, arguments) = match &node.kind {
+            NodeKind::Directive(directive) => {
+                (self.items(&directive.signature)?, Some(self.items(&directive.arguments)?))
+            },
+            NodeKind::Statement(statement) => (self.items(&statement.signature)?, None),
+        };
+        // The comment ends the node's line, so it is resolved before the children.
+        let comment = match &node.comment {
+            Some(comment) => {
+                Some(Comment { text: comment.text.clone(), location: self.span(comment.location)? })
+            },
+            None => None,
+        };
+        let kind = match arguments {
+            Some(arguments) => NodeKind::Directive(Directive {
+                signature,
+                arguments,
+                children: node.children().iter()
+                    .map(|child| self.node(child))
+                    .collect::<Option<_>>()?,
+            }),
+            None => NodeKind::Statement(Statement { signature }),
+        };
+        Some(Node { kind, location, docs, comment })
+    }
+
+    fn items(&mut self, items: &[Item]) -> Option<Vec<Item>> {
+        items.iter().map(|item| self.item(item)).collect()
+    }
+
+    fn item(&mut self, item: &Item) -> Option<Item> {
+        if let ItemKind::Pair { key, separator, value } = &item.kind {
+            // The pair location ends after the key, so the key is resolved first.
+            let start = self.offset(item.location.start())?;
+            let key = Box::new(self.item(key)?);
+            let location = start.span(self.offset(item.location.end())?);
+            let value = Box::new(self.item(value)?);
+            let kind = ItemKind::Pair { key, separator: *separator, value };
+            return Some(Item { kind, location });
+        }
+        let location = self.span(item.location)?;
+        let kind = match &item.kind {
+            ItemKind::Parentheses(items) => ItemKind::Parentheses(self.items(items)?),
+            ItemKind::Brackets(items) => ItemKind::Brackets(self.items(items)?),
+            ItemKind::Braces(items) => ItemKind::Braces(self.items(items)?),
+            kind => kind.clone(),
+        };
+        Some(Item { kind, location })
+    }
+
+    fn span(&mut self, span: Span) -> Option<Span> {
+        let start = self.offset(span.start())?;
+        let end = self.offset(span.end())?;
+        Some(start.span(end))
+    }
+
+    fn offset(&mut self, offset: Offset) -> Option<Offset> {
+        let index = self.old.byte_index(offset)? as isize + self.shift;
+        Some(self.new.skip(usize::try_from(index).ok()?).offset())
+    }
+}
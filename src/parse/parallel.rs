@@ -0,0 +1,59 @@
+use rayon::prelude::*;
+use src_ctx::Input;
+
+use crate::Tree;
+
+use super::{ParseOptions, ParseResult, parse_input_with};
+use super::incremental::{has_skipped_lines, root_line_starts};
+
+
+/// Parse many inputs concurrently with the same [`ParseOptions`].
+///
+/// Each input is given with a key, usually the index of its source in a
+/// [`SourceMap`](src_ctx::SourceMap). The results are returned in the order of the inputs,
+/// independent of the order in which they were parsed. Limits apply to each input separately.
+pub fn parse_all<'a, K, I>(inputs: I, options: ParseOptions) -> Vec<(K, ParseResult<Tree>)>
+where
+    K: Send,
+    I: IntoIterator<Item = (K, Input<'a>)>,
+{
+    let inputs: Vec<_> = inputs.into_iter().collect();
+    inputs.into_par_iter()
+        .map(|(key, input)| (key, parse_input_with(input, options)))
+        .collect()
+}
+
+pub(crate) fn parse_split_input(input: Input<'_>, options: ParseOptions) -> ParseResult<Tree> {
+    // The node limit applies to the whole tree, not to each of the parts.
+    if has_skipped_lines(&input) || options.max_nodes.is_some() {
+        return parse_input_with(input, options);
+    }
+    let regions = split_regions(&input, rayon::current_num_threads());
+    if regions.len() < 2 {
+        return parse_input_with(input, options);
+    }
+    let trees: Vec<_> = regions.into_par_iter()
+        .map(|(start, end)| parse_input_with(input.skip(start).truncate(end - start), options))
+        .collect();
+    let mut roots = Vec::new();
+    for tree in trees {
+        roots.extend(tree?.roots);
+    }
+    Ok(Tree { roots })
+}
+
+/// Split the input at root lines into about `count` regions of similar size.
+fn split_regions(input: &Input<'_>, count: usize) -> Vec<(usize, usize)> {
+    let len = input.content().len();
+    let target = len / count.max(1) + 1;
+    let mut regions = Vec::new();
+    let mut start = 0;
+    for root_start in root_line_starts(input) {
+        if root_start - start >= target {
+            regions.push((start, root_start));
+            start = root_start;
+        }
+    }
+    regions.push((start, len));
+    regions
+}
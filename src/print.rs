@@ -0,0 +1,110 @@
+use std::fmt;
+
+use crate::{Tree, Node, NodeKind, Item, ItemKind, Indent};
+
+
+impl Tree {
+    /// Render the tree as source text using the given [`Indent`].
+    ///
+    /// Doc comments and recorded trailing comments are written, other comments and formatting
+    /// are not preserved. Booleans and null are written as `true`, `false` and `null`. Parsing
+    /// the output with the same options produces an equal tree except for locations, as long as
+    /// any [`Literals`](crate::Literals) include these words.
+    pub fn to_source(&self, indent: Indent) -> String {
+        let mut source = String::new();
+        for root in &self.roots {
+            write_node(&mut source, root, indent, 0);
+        }
+        source
+    }
+}
+
+pub(crate) fn write_node(out: &mut String, node: &Node, indent: Indent, depth: usize) {
+    for doc in &node.docs {
+        indent.write_prefix(out, depth);
+        out.push_str(";;");
+        if !doc.text.is_empty() {
+            out.push(' ');
+            out.push_str(&doc.text);
+        }
+        out.push('\n');
+    }
+    indent.write_prefix(out, depth);
+    match &node.kind {
+        NodeKind::Directive(directive) => {
+            write_items(out, &directive.signature);
+            out.push(':');
+            if !directive.arguments.is_empty() {
+                out.push(' ');
+                write_items(out, &directive.arguments);
+            }
+            write_comment(out, node);
+            out.push('\n');
+            for child in &directive.children {
+                write_node(out, child, indent, depth + 1);
+            }
+        },
+        NodeKind::Statement(statement) => {
+            write_items(out, &statement.signature);
+            write_comment(out, node);
+            out.push('\n');
+        },
+    }
+}
+
+fn write_comment(out: &mut String, node: &Node) {
+    if let Some(comment) = &node.comment {
+        out.push_str(" ;");
+        if !comment.text.is_empty() {
+            out.push(' ');
+            out.push_str(&comment.text);
+        }
+    }
+}
+
+fn write_items(out: &mut String, items: &[Item]) {
+    use fmt::Write;
+    write!(out, "{}", Items(items)).unwrap();
+}
+
+struct Items<'a>(&'a [Item]);
+
+impl fmt::Display for Items<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, item) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            item.kind.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays the item in source form.
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+/// Displays the item kind in source form, with booleans and null as `true`, `false` and `null`.
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(word) => f.write_str(word),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Float(value) if value.is_finite() && value.fract() == 0.0 => {
+                write!(f, "{}.0", value)
+            },
+            Self::Float(value) => write!(f, "{}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Null => f.write_str("null"),
+            Self::Sigil { sigil, name } => write!(f, "{}{}", sigil, name),
+            Self::Parentheses(items) => write!(f, "({})", Items(items)),
+            Self::Brackets(items) => write!(f, "[{}]", Items(items)),
+            Self::Braces(items) => write!(f, "{{{}}}", Items(items)),
+            Self::Pair { key, separator, value } => write!(f, "{}{}{}", key, separator, value),
+        }
+    }
+}
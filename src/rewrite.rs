@@ -0,0 +1,238 @@
+//! Changes to a parsed source as minimal [`TextEdit`] values.
+//!
+//! The edits are computed from the locations in a [`Tree`](crate::Tree) parsed from the input
+//! given to the [`Rewriter`], and refer to byte ranges in that original input. Comments and
+//! formatting outside of the changed ranges are preserved.
+use smol_str::SmolStr;
+use src_ctx::{Input, SourceError, Offset};
+
+use crate::{Node, NodeKind, Item, ItemKind, Indent, Locator, ParseOptions, TextEdit};
+use crate::parse::parses_as_word;
+use crate::print::write_node;
+
+
+/// Type alias for [`Result`] with [`RewriteError`].
+pub type RewriteResult<T = ()> = Result<T, SourceError<RewriteError>>;
+
+/// Errors encountered by a [`Rewriter`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RewriteError {
+    #[error("Location is not part of the rewritten input")]
+    UnknownLocation,
+    #[error("Change overlaps an earlier change")]
+    Overlapping,
+    #[error("Expected a word item")]
+    ExpectedWord,
+    #[error("Invalid word `{word}`")]
+    InvalidWord { word: SmolStr },
+    #[error("Expected a directive node")]
+    ExpectedDirective,
+    #[error("Child position {position} is out of bounds")]
+    InvalidPosition { position: usize },
+}
+
+/// Collects changes to an input as [`TextEdit`] values.
+///
+/// Changes may not overlap. Nodes inserted at the same position keep their order.
+#[derive(Clone)]
+pub struct Rewriter<'a> {
+    locator: Locator<'a>,
+    options: ParseOptions,
+    edits: Vec<TextEdit>,
+}
+
+impl<'a> Rewriter<'a> {
+    /// Create a rewriter for an input indented with `indent`.
+    pub fn new(input: Input<'a>, indent: Indent) -> Self {
+        Self::with_options(input, ParseOptions::new(indent))
+    }
+
+    /// Create a rewriter for an input parsed with the given [`ParseOptions`].
+    pub fn with_options(input: Input<'a>, options: ParseOptions) -> Self {
+        Self { locator: Locator::new(input), options, edits: Vec::new() }
+    }
+
+    /// Replace a word item with a different word.
+    ///
+    /// The new word has to be parsed back as a word with the rewriter's options, so it cannot
+    /// be a literal, start with a sigil or contain the pair separator.
+    pub fn rename_word(&mut self, item: &Item, word: &str) -> RewriteResult {
+        if item.word().is_none() {
+            return Err(SourceError::new(
+                RewriteError::ExpectedWord,
+                item.location.start(),
+                "not a word",
+            ));
+        }
+        if !parses_as_word(word, &self.options) {
+            return Err(SourceError::new(
+                RewriteError::InvalidWord { word: word.into() },
+                item.location.start(),
+                "renamed here",
+            ));
+        }
+        let range = self.item_range(item)?;
+        self.push(range, word, item.location.start())
+    }
+
+    /// Replace an item, including any items inside of it, with the source form of another.
+    pub fn replace_item(&mut self, item: &Item, replacement: &Item) -> RewriteResult {
+        let range = self.item_range(item)?;
+        self.push(range, &replacement.to_string(), item.location.start())
+    }
+
+    /// Remove a node with its doc comments and all of its children.
+    pub fn remove_node(&mut self, node: &Node) -> RewriteResult {
+        let start = self.node_start(node)?;
+        let (end, _) = self.subtree_end(node)?;
+        self.push(start..end, "", node.location)
+    }
+
+    /// Insert `child` as the child at `position` of a directive `parent`.
+    pub fn insert_child(&mut self, parent: &Node, position: usize, child: &Node) -> RewriteResult {
+        let NodeKind::Directive(directive) = &parent.kind else {
+            return Err(SourceError::new(
+                RewriteError::ExpectedDirective,
+                parent.location,
+                "not a directive",
+            ));
+        };
+        if position > directive.children.len() {
+            return Err(SourceError::new(
+                RewriteError::InvalidPosition { position },
+                parent.location,
+                "inserted here",
+            ));
+        }
+
+        let parent_start = self.byte_index(parent.location)?;
+        let parent_line_start = self.line_start(parent.location)?;
+        let mut prefix = self.content()[parent_line_start..parent_start].to_string();
+        self.options.indent().write_prefix(&mut prefix, 1);
+
+        let mut rendered = String::new();
+        write_node(&mut rendered, child, self.options.indent(), 0);
+        let mut text = String::new();
+        for line in rendered.lines() {
+            text.push_str(&prefix);
+            text.push_str(line);
+            text.push('\n');
+        }
+
+        let index = match directive.children.get(position) {
+            Some(next) => self.node_start(next)?,
+            None => {
+                let (end, has_newline) = self.subtree_end(parent)?;
+                if !has_newline {
+                    text.insert(0, '\n');
+                    text.pop();
+                }
+                end
+            },
+        };
+        self.push(index..index, &text, parent.location)
+    }
+
+    /// The collected edits, ordered by their position in the input.
+    pub fn into_edits(self) -> Vec<TextEdit> {
+        let mut edits = self.edits;
+        edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+        edits
+    }
+
+    /// The input with all collected edits applied.
+    pub fn to_source(&self) -> String {
+        let mut source = self.content().to_string();
+        for edit in self.clone().into_edits().iter().rev() {
+            source.replace_range(edit.range.clone(), &edit.replacement);
+        }
+        source
+    }
+
+    fn content(&self) -> &str {
+        self.locator.input().content()
+    }
+
+    fn push(
+        &mut self,
+        range: std::ops::Range<usize>,
+        replacement: &str,
+        location: Offset,
+    ) -> RewriteResult {
+        // Insertions at the same index do not overlap, but an insertion inside of a replaced
+        // range does.
+        let overlaps = self.edits.iter()
+            .any(|edit| edit.range.start < range.end && range.start < edit.range.end);
+        if overlaps {
+            return Err(SourceError::new(RewriteError::Overlapping, location, "changed here"));
+        }
+        self.edits.push(TextEdit::new(range, replacement));
+        Ok(())
+    }
+
+    fn byte_index(&mut self, offset: Offset) -> RewriteResult<usize> {
+        self.locator.byte_index(offset).ok_or_else(|| SourceError::new(
+            RewriteError::UnknownLocation,
+            offset,
+            "unknown location",
+        ))
+    }
+
+    /// The byte index of the start of the line containing `offset`.
+    fn line_start(&mut self, offset: Offset) -> RewriteResult<usize> {
+        let index = self.byte_index(offset)?;
+        Ok(self.content()[..index].rfind('\n').map_or(0, |newline| newline + 1))
+    }
+
+    /// The byte index of the start of the first line of a node, including its doc comments.
+    fn node_start(&mut self, node: &Node) -> RewriteResult<usize> {
+        match node.docs.first() {
+            Some(doc) => self.line_start(doc.location.start()),
+            None => self.line_start(node.location),
+        }
+    }
+
+    /// The byte index after the last line of a node and its descendants, and whether that
+    /// line ends with a newline.
+    fn subtree_end(&mut self, node: &Node) -> RewriteResult<(usize, bool)> {
+        let mut last = node;
+        while let Some(child) = last.children().last() {
+            last = child;
+        }
+        let index = self.byte_index(last.location)?;
+        let content = self.content();
+        Ok(match content[index..].find('\n') {
+            Some(newline) => (index + newline + 1, true),
+            None => (content.len(), false),
+        })
+    }
+
+    /// The byte range of an item, including the closing character of groups and the value of
+    /// pairs.
+    fn item_range(&mut self, item: &Item) -> RewriteResult<std::ops::Range<usize>> {
+        let range = self.locator.byte_range(item.location).ok_or_else(|| SourceError::new(
+            RewriteError::UnknownLocation,
+            item.location.start(),
+            "unknown location",
+        ))?;
+        if let ItemKind::Pair { value, .. } = &item.kind {
+            return Ok(range.start..self.item_range(value)?.end);
+        }
+        if !(item.is_parenthesized() || item.is_bracketed() || item.is_braced()) {
+            return Ok(range);
+        }
+        let content = self.content();
+        let mut depth = 0_usize;
+        for (index, c) in content[range.start..].char_indices() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {},
+            }
+            if depth == 0 {
+                return Ok(range.start..(range.start + index + c.len_utf8()));
+            }
+        }
+        Ok(range.start..content.len())
+    }
+}
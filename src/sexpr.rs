@@ -0,0 +1,525 @@
+//! Conversion between [`Tree`] values and S-expressions.
+//!
+//! # Mapping
+//!
+//! | treelang                        | S-expression                               |
+//! |---------------------------------|--------------------------------------------|
+//! | [`Tree`]                        | `(<node>...)`                              |
+//! | [`Statement`](crate::Statement) | `((<item>...))`                            |
+//! | [`Directive`](crate::Directive) | `((<item>...) (<item>...) (<node>...))`    |
+//! | [`ItemKind::Word`]              | `word`, or `"word"` if quoting is required |
+//! | [`ItemKind::Int`]               | `23`                                       |
+//! | [`ItemKind::Float`]             | `23.5`                                     |
+//! | [`ItemKind::Bool`]              | `(bool true)` or `(bool false)`            |
+//! | [`ItemKind::Null`]              | `(null)`                                   |
+//! | [`ItemKind::Sigil`]             | `(sigil $ name)`                           |
+//! | [`ItemKind::Parentheses`]       | `(parentheses <item>...)`                  |
+//! | [`ItemKind::Brackets`]          | `(brackets <item>...)`                     |
+//! | [`ItemKind::Braces`]            | `(braces <item>...)`                       |
+//! | [`ItemKind::Pair`]              | `(pair <key> <separator> <item>)`          |
+//!
+//! Statements are lists holding only their signature, directives are lists holding their
+//! signature, arguments and children. Nodes with doc comments or a trailing comment end in
+//! `:docs (<text>...)` and `:comment <text>` entries.
+//!
+//! Since every tree node carries a source location, S-expressions are converted to source
+//! text, which can then be parsed with [`Tree::parse_with`]. Literals, sigils and trailing
+//! comments only convert back to the same tree with matching [`ParseOptions`].
+use std::fmt;
+
+use smol_str::SmolStr;
+
+use crate::{Tree, Node, NodeKind, Item, ItemKind, Indent};
+#[cfg(doc)]
+use crate::ParseOptions;
+
+
+const PARENTHESES: &str = "parentheses";
+const BRACKETS: &str = "brackets";
+const BRACES: &str = "braces";
+const PAIR: &str = "pair";
+const BOOL: &str = "bool";
+const NULL: &str = "null";
+const SIGIL: &str = "sigil";
+const DOCS: &str = ":docs";
+const COMMENT: &str = ":comment";
+
+const QUOTE: char = '"';
+const ESCAPE: char = '\\';
+
+/// An S-expression value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sexpr {
+    Word(SmolStr),
+    Int(i32),
+    Float(f32),
+    List(Vec<Sexpr>),
+}
+
+/// Errors encountered when parsing or converting a [`Sexpr`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SexprError {
+    #[error("Unexpected character `{unexpected}` at byte {index}")]
+    UnexpectedChar { unexpected: char, index: usize },
+    #[error("Unexpected end of input")]
+    UnexpectedEnd,
+    #[error("Invalid number `{value}` at byte {index}")]
+    InvalidNumber { value: SmolStr, index: usize },
+    #[error("List at byte {index} is nested deeper than {limit} levels")]
+    DepthLimit { limit: usize, index: usize },
+    #[error("Expected a statement or directive list")]
+    ExpectedNode,
+    #[error("Expected a word, number, group or pair list")]
+    ExpectedItem,
+    #[error("Expected a list")]
+    ExpectedList,
+    #[error("Empty node signature")]
+    EmptySignature,
+    #[error("Word `{value}` is not a valid treelang word")]
+    InvalidWord { value: SmolStr },
+    #[error("Expected a single line comment word")]
+    InvalidComment,
+}
+
+impl Tree {
+    /// Convert the tree into an S-expression.
+    pub fn to_sexpr(&self) -> Sexpr {
+        Sexpr::List(self.iter().map(node_to_sexpr).collect())
+    }
+}
+
+impl Sexpr {
+    /// The deepest list nesting accepted by [`Sexpr::parse`].
+    pub const MAX_DEPTH: usize = 512;
+
+    /// Parse a single S-expression from a `&str`.
+    ///
+    /// Comments start with a `;` and extend to the end of the line. Lists nested deeper than
+    /// [`Sexpr::MAX_DEPTH`] are rejected.
+    pub fn parse(input: &str) -> Result<Self, SexprError> {
+        let mut parser = Parser { input, index: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            Some(unexpected) => Err(SexprError::UnexpectedChar { unexpected, index: parser.index }),
+            None => Ok(value),
+        }
+    }
+
+    /// Convert a tree S-expression into source text with the given [`Indent`].
+    pub fn to_source(&self, indent: Indent) -> Result<String, SexprError> {
+        let mut source = String::new();
+        for node in expect_list(self)? {
+            write_node(&mut source, node, indent, 0)?;
+        }
+        Ok(source)
+    }
+
+    /// Returns the elements if this is a [`Sexpr::List`].
+    pub fn list(&self) -> Option<&[Sexpr]> {
+        if let Self::List(values) = self {
+            Some(values)
+        } else {
+            None
+        }
+    }
+}
+
+/// Displays the S-expression on a single line.
+///
+/// The alternate form (`{:#}`) breaks lists containing other lists over multiple lines, keeping
+/// leading words on the first line and aligning the remaining elements.
+impl fmt::Display for Sexpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let mut out = String::new();
+            write_pretty(&mut out, self);
+            f.write_str(&out)
+        } else {
+            write_compact(f, self)
+        }
+    }
+}
+
+fn write_compact(f: &mut impl fmt::Write, value: &Sexpr) -> fmt::Result {
+    match value {
+        Sexpr::Word(word) if needs_quotes(word) => {
+            f.write_char(QUOTE)?;
+            for c in word.chars() {
+                if c == QUOTE || c == ESCAPE {
+                    f.write_char(ESCAPE)?;
+                }
+                f.write_char(c)?;
+            }
+            f.write_char(QUOTE)
+        },
+        Sexpr::Word(word) => f.write_str(word),
+        Sexpr::Int(value) => write!(f, "{}", value),
+        Sexpr::Float(value) => write!(f, "{}", ItemKind::Float(*value)),
+        Sexpr::List(values) => {
+            f.write_char('(')?;
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    f.write_char(' ')?;
+                }
+                write_compact(f, value)?;
+            }
+            f.write_char(')')
+        },
+    }
+}
+
+fn write_pretty(out: &mut String, value: &Sexpr) {
+    let values = match value {
+        Sexpr::List(values) if values.iter().any(|value| value.list().is_some()) => values,
+        _ => {
+            write_compact(out, value).unwrap();
+            return;
+        },
+    };
+    let column = out[out.rfind('\n').map_or(0, |index| index + 1)..].chars().count();
+    let mut is_head = true;
+    out.push('(');
+    for (index, value) in values.iter().enumerate() {
+        if index > 0 {
+            if is_head && value.list().is_none() {
+                out.push(' ');
+            } else {
+                out.push('\n');
+                out.push_str(&" ".repeat(column + 1));
+            }
+        }
+        if value.list().is_some() {
+            is_head = false;
+        }
+        write_pretty(out, value);
+    }
+    out.push(')');
+}
+
+fn needs_quotes(word: &str) -> bool {
+    word.is_empty()
+        || word.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+        || word.contains(|c: char| c.is_whitespace() || "()\";'`,|\\".contains(c))
+}
+
+fn node_to_sexpr(node: &Node) -> Sexpr {
+    let mut values = match &node.kind {
+        NodeKind::Statement(statement) => vec![items_to_sexpr(&statement.signature)],
+        NodeKind::Directive(directive) => vec![
+            items_to_sexpr(&directive.signature),
+            items_to_sexpr(&directive.arguments),
+            Sexpr::List(directive.children.iter().map(node_to_sexpr).collect()),
+        ],
+    };
+    if !node.docs.is_empty() {
+        values.push(Sexpr::Word(DOCS.into()));
+        let docs = node.docs.iter().map(|doc| Sexpr::Word(doc.text.clone()));
+        values.push(Sexpr::List(docs.collect()));
+    }
+    if let Some(comment) = &node.comment {
+        values.push(Sexpr::Word(COMMENT.into()));
+        values.push(Sexpr::Word(comment.text.clone()));
+    }
+    Sexpr::List(values)
+}
+
+fn items_to_sexpr(items: &[Item]) -> Sexpr {
+    Sexpr::List(items.iter().map(item_to_sexpr).collect())
+}
+
+fn item_to_sexpr(item: &Item) -> Sexpr {
+    let group = |tag: &str, items: &[Item]| {
+        let tag = Sexpr::Word(tag.into());
+        Sexpr::List(std::iter::once(tag).chain(items.iter().map(item_to_sexpr)).collect())
+    };
+    match &item.kind {
+        ItemKind::Word(word) => Sexpr::Word(word.clone()),
+        ItemKind::Int(value) => Sexpr::Int(*value),
+        ItemKind::Float(value) => Sexpr::Float(*value),
+        ItemKind::Bool(value) => Sexpr::List(vec![
+            Sexpr::Word(BOOL.into()),
+            Sexpr::Word(if *value { "true" } else { "false" }.into()),
+        ]),
+        ItemKind::Null => Sexpr::List(vec![Sexpr::Word(NULL.into())]),
+        ItemKind::Sigil { sigil, name } => Sexpr::List(vec![
+            Sexpr::Word(SIGIL.into()),
+            Sexpr::Word(sigil.to_string().into()),
+            Sexpr::Word(name.clone()),
+        ]),
+        ItemKind::Parentheses(items) => group(PARENTHESES, items),
+        ItemKind::Brackets(items) => group(BRACKETS, items),
+        ItemKind::Braces(items) => group(BRACES, items),
+        ItemKind::Pair { key, separator, value } => Sexpr::List(vec![
+            Sexpr::Word(PAIR.into()),
+            item_to_sexpr(key),
+            Sexpr::Word(separator.to_string().into()),
+            item_to_sexpr(value),
+        ]),
+    }
+}
+
+fn expect_list(value: &Sexpr) -> Result<&[Sexpr], SexprError> {
+    value.list().ok_or(SexprError::ExpectedList)
+}
+
+fn write_node(out: &mut String, node: &Sexpr, indent: Indent, depth: usize) -> Result<(), SexprError> {
+    let values = node.list().ok_or(SexprError::ExpectedNode)?;
+    let (values, extras) = values.split_at(
+        values.iter().position(is_keyword).unwrap_or(values.len()),
+    );
+    let (signature, rest) = match values {
+        [signature, rest @ ..] if rest.is_empty() || rest.len() == 2 => (signature, rest),
+        _ => return Err(SexprError::ExpectedNode),
+    };
+    let (docs, comment) = node_extras(extras)?;
+    let signature = expect_list(signature)?;
+    if signature.is_empty() {
+        return Err(SexprError::EmptySignature);
+    }
+    for doc in docs {
+        indent.write_prefix(out, depth);
+        write_comment(out, ";;", doc)?;
+        out.push('\n');
+    }
+    indent.write_prefix(out, depth);
+    write_items(out, signature)?;
+    if let [arguments, children] = rest {
+        let arguments = expect_list(arguments)?;
+        out.push(':');
+        if !arguments.is_empty() {
+            out.push(' ');
+            write_items(out, arguments)?;
+        }
+        if let Some(comment) = comment {
+            write_comment(out, " ;", comment)?;
+        }
+        out.push('\n');
+        for child in expect_list(children)? {
+            write_node(out, child, indent, depth + 1)?;
+        }
+    } else {
+        if let Some(comment) = comment {
+            write_comment(out, " ;", comment)?;
+        }
+        out.push('\n');
+    }
+    Ok(())
+}
+
+fn is_keyword(value: &Sexpr) -> bool {
+    matches!(value, Sexpr::Word(word) if word.starts_with(':'))
+}
+
+/// Split the `:docs` and `:comment` entries at the end of a node list.
+fn node_extras(extras: &[Sexpr]) -> Result<(&[Sexpr], Option<&Sexpr>), SexprError> {
+    let mut docs: &[Sexpr] = &[];
+    let mut comment = None;
+    for entry in extras.chunks(2) {
+        match entry {
+            [Sexpr::Word(key), Sexpr::List(texts)] if key == DOCS => docs = texts,
+            [Sexpr::Word(key), text] if key == COMMENT => comment = Some(text),
+            _ => return Err(SexprError::ExpectedNode),
+        }
+    }
+    Ok((docs, comment))
+}
+
+fn write_comment(out: &mut String, marker: &str, text: &Sexpr) -> Result<(), SexprError> {
+    let text = match text {
+        Sexpr::Word(text) if !text.contains(['\n', '\r']) => text,
+        _ => return Err(SexprError::InvalidComment),
+    };
+    out.push_str(marker);
+    if !text.is_empty() {
+        out.push(' ');
+        out.push_str(text);
+    }
+    Ok(())
+}
+
+fn write_items(out: &mut String, items: &[Sexpr]) -> Result<(), SexprError> {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            out.push(' ');
+        }
+        write_item(out, item)?;
+    }
+    Ok(())
+}
+
+fn write_item(out: &mut String, item: &Sexpr) -> Result<(), SexprError> {
+    match item {
+        Sexpr::Word(word) => {
+            if !crate::parse::is_valid_word(word) {
+                return Err(SexprError::InvalidWord { value: word.clone() });
+            }
+            out.push_str(word);
+        },
+        Sexpr::Int(value) => out.push_str(&value.to_string()),
+        Sexpr::Float(value) => out.push_str(&ItemKind::Float(*value).to_string()),
+        Sexpr::List(values) => {
+            match values.as_slice() {
+                [Sexpr::Word(tag), key, separator, value] if tag == PAIR => {
+                    return write_pair(out, key, separator, value);
+                },
+                [Sexpr::Word(tag), Sexpr::Word(value)]
+                    if tag == BOOL && (value == "true" || value == "false") =>
+                {
+                    out.push_str(value);
+                    return Ok(());
+                },
+                [Sexpr::Word(tag)] if tag == NULL => {
+                    out.push_str("null");
+                    return Ok(());
+                },
+                [Sexpr::Word(tag), sigil, Sexpr::Word(name)] if tag == SIGIL => {
+                    let sigil = single_char(sigil).ok_or(SexprError::ExpectedItem)?;
+                    let word = format!("{}{}", sigil, name);
+                    if name.is_empty() || !crate::parse::is_valid_word(&word) {
+                        return Err(SexprError::InvalidWord { value: word.into() });
+                    }
+                    out.push_str(&word);
+                    return Ok(());
+                },
+                _ => {},
+            }
+            let (open, close, items) = match values.as_slice() {
+                [Sexpr::Word(tag), items @ ..] if tag == PARENTHESES => ('(', ')', items),
+                [Sexpr::Word(tag), items @ ..] if tag == BRACKETS => ('[', ']', items),
+                [Sexpr::Word(tag), items @ ..] if tag == BRACES => ('{', '}', items),
+                _ => return Err(SexprError::ExpectedItem),
+            };
+            out.push(open);
+            write_items(out, items)?;
+            out.push(close);
+        },
+    }
+    Ok(())
+}
+
+fn write_pair(
+    out: &mut String,
+    key: &Sexpr,
+    separator: &Sexpr,
+    value: &Sexpr,
+) -> Result<(), SexprError> {
+    let (Sexpr::Word(key), Some(separator)) = (key, single_char(separator)) else {
+        return Err(SexprError::ExpectedItem);
+    };
+    if !crate::parse::is_valid_pair_key(key, separator) {
+        return Err(SexprError::InvalidWord { value: key.clone() });
+    }
+    out.push_str(key);
+    out.push(separator);
+    write_item(out, value)
+}
+
+fn single_char(value: &Sexpr) -> Option<char> {
+    let Sexpr::Word(word) = value else {
+        return None;
+    };
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some(char), None) => Some(char),
+        _ => None,
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    index: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.index..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.index += rest.len() - trimmed.len();
+            if trimmed.starts_with(';') {
+                self.index += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Sexpr, SexprError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err(SexprError::UnexpectedEnd),
+            Some('(') => {
+                if self.depth == Sexpr::MAX_DEPTH {
+                    let limit = Sexpr::MAX_DEPTH;
+                    return Err(SexprError::DepthLimit { limit, index: self.index });
+                }
+                self.index += 1;
+                self.depth += 1;
+                let mut values = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(')') => {
+                            self.index += 1;
+                            self.depth -= 1;
+                            return Ok(Sexpr::List(values));
+                        },
+                        _ => values.push(self.value()?),
+                    }
+                }
+            },
+            Some(QUOTE) => {
+                self.index += 1;
+                let mut word = String::new();
+                let mut chars = self.rest().chars();
+                loop {
+                    let c = chars.next().ok_or(SexprError::UnexpectedEnd)?;
+                    self.index += c.len_utf8();
+                    match c {
+                        QUOTE => return Ok(Sexpr::Word(word.into())),
+                        ESCAPE => {
+                            let escaped = chars.next().ok_or(SexprError::UnexpectedEnd)?;
+                            self.index += escaped.len_utf8();
+                            word.push(escaped);
+                        },
+                        c => word.push(c),
+                    }
+                }
+            },
+            Some(unexpected @ ')') => {
+                Err(SexprError::UnexpectedChar { unexpected, index: self.index })
+            },
+            Some(_) => {
+                let start = self.index;
+                let rest = self.rest();
+                let len = rest.find(|c: char| c.is_whitespace() || "()\";".contains(c))
+                    .unwrap_or(rest.len());
+                let atom = &rest[..len];
+                self.index += len;
+                let is_number = atom.starts_with(|c: char| c.is_ascii_digit())
+                    || (atom.starts_with('-') && atom.len() > 1);
+                if !is_number {
+                    Ok(Sexpr::Word(atom.into()))
+                } else if atom.contains('.') {
+                    atom.parse().map(Sexpr::Float).map_err(|_| invalid_number(atom, start))
+                } else {
+                    atom.parse().map(Sexpr::Int).map_err(|_| invalid_number(atom, start))
+                }
+            },
+        }
+    }
+}
+
+fn invalid_number(value: &str, index: usize) -> SexprError {
+    SexprError::InvalidNumber { value: value.into(), index }
+}
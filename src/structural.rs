@@ -0,0 +1,272 @@
+//! Equality and hashing ignoring source locations.
+//!
+//! Doc comments and trailing comments are ignored as well, so adding documentation does not
+//! change stable hashes.
+//!
+//! Floats are compared by their bits, except that all NaN values are equal to each other and
+//! `0.0` is equal to `-0.0`. This makes the equality reflexive, so [`Structural`] can implement
+//! [`Eq`] and be used as a [`HashMap`](std::collections::HashMap) key.
+use std::hash::{Hash, Hasher};
+
+use crate::{Tree, Node, NodeKind, Directive, Statement, Item, ItemKind};
+
+
+/// Equality and hashing of values ignoring their locations.
+pub trait StructuralEq {
+    /// Returns `true` if both values are equal apart from their locations.
+    fn structural_eq(&self, other: &Self) -> bool;
+
+    /// Feed the value into a [`Hasher`], ignoring locations.
+    ///
+    /// Only explicit byte sequences are written, so the result does not depend on the platform.
+    fn structural_hash<H: Hasher>(&self, state: &mut H);
+
+    /// A hash of the value that is stable across platforms and releases.
+    fn stable_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        self.structural_hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Wrapper implementing [`Eq`] and [`Hash`] with [`StructuralEq`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Structural<T>(pub T);
+
+impl<T: StructuralEq> PartialEq for Structural<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.structural_eq(&other.0)
+    }
+}
+
+impl<T: StructuralEq> Eq for Structural<T> {}
+
+impl<T: StructuralEq> Hash for Structural<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.structural_hash(state);
+    }
+}
+
+/// The 64 bit FNV-1a hash function.
+#[derive(Debug, Clone)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self { state: 0xcbf2_9ce4_8422_2325 }
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= u64::from(byte);
+            self.state = self.state.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+impl<T: StructuralEq + ?Sized> StructuralEq for &T {
+    fn structural_eq(&self, other: &Self) -> bool {
+        (**self).structural_eq(*other)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        (**self).structural_hash(state);
+    }
+}
+
+impl<T: StructuralEq> StructuralEq for [T] {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.structural_eq(b))
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        write_len(state, self.len());
+        for value in self {
+            value.structural_hash(state);
+        }
+    }
+}
+
+impl<T: StructuralEq> StructuralEq for Vec<T> {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self[..].structural_eq(&other[..])
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        self[..].structural_hash(state);
+    }
+}
+
+impl StructuralEq for Tree {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.roots.structural_eq(&other.roots)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        self.roots.structural_hash(state);
+    }
+}
+
+impl StructuralEq for Node {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.kind.structural_eq(&other.kind)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        self.kind.structural_hash(state);
+    }
+}
+
+impl StructuralEq for NodeKind {
+    fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Directive(a), Self::Directive(b)) => a.structural_eq(b),
+            (Self::Statement(a), Self::Statement(b)) => a.structural_eq(b),
+            _ => false,
+        }
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::Directive(directive) => {
+                state.write_u8(0);
+                directive.structural_hash(state);
+            },
+            Self::Statement(statement) => {
+                state.write_u8(1);
+                statement.structural_hash(state);
+            },
+        }
+    }
+}
+
+impl StructuralEq for Directive {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.signature.structural_eq(&other.signature)
+            && self.arguments.structural_eq(&other.arguments)
+            && self.children.structural_eq(&other.children)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        self.signature.structural_hash(state);
+        self.arguments.structural_hash(state);
+        self.children.structural_hash(state);
+    }
+}
+
+impl StructuralEq for Statement {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.signature.structural_eq(&other.signature)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        self.signature.structural_hash(state);
+    }
+}
+
+impl StructuralEq for Item {
+    fn structural_eq(&self, other: &Self) -> bool {
+        self.kind.structural_eq(&other.kind)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        self.kind.structural_hash(state);
+    }
+}
+
+impl StructuralEq for ItemKind {
+    fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Word(a), Self::Word(b)) => a == b,
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => float_bits(*a) == float_bits(*b),
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Null, Self::Null) => true,
+            (
+                Self::Sigil { sigil: a_sigil, name: a_name },
+                Self::Sigil { sigil: b_sigil, name: b_name },
+            ) => a_sigil == b_sigil && a_name == b_name,
+            (Self::Parentheses(a), Self::Parentheses(b))
+            | (Self::Brackets(a), Self::Brackets(b))
+            | (Self::Braces(a), Self::Braces(b)) => a.structural_eq(b),
+            (
+                Self::Pair { key: a_key, separator: a_separator, value: a_value },
+                Self::Pair { key: b_key, separator: b_separator, value: b_value },
+            ) => {
+                a_separator == b_separator
+                    && a_key.structural_eq(b_key)
+                    && a_value.structural_eq(b_value)
+            },
+            _ => false,
+        }
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::Word(word) => {
+                state.write_u8(0);
+                write_len(state, word.len());
+                state.write(word.as_bytes());
+            },
+            Self::Int(value) => {
+                state.write_u8(1);
+                state.write(&value.to_le_bytes());
+            },
+            Self::Float(value) => {
+                state.write_u8(2);
+                state.write(&float_bits(*value).to_le_bytes());
+            },
+            Self::Parentheses(items) => {
+                state.write_u8(3);
+                items.structural_hash(state);
+            },
+            Self::Brackets(items) => {
+                state.write_u8(4);
+                items.structural_hash(state);
+            },
+            Self::Braces(items) => {
+                state.write_u8(5);
+                items.structural_hash(state);
+            },
+            Self::Bool(value) => {
+                state.write_u8(7);
+                state.write_u8(u8::from(*value));
+            },
+            Self::Null => state.write_u8(8),
+            Self::Sigil { sigil, name } => {
+                state.write_u8(9);
+                state.write(&u32::from(*sigil).to_le_bytes());
+                write_len(state, name.len());
+                state.write(name.as_bytes());
+            },
+            Self::Pair { key, separator, value } => {
+                state.write_u8(6);
+                state.write(&u32::from(*separator).to_le_bytes());
+                key.structural_hash(state);
+                value.structural_hash(state);
+            },
+        }
+    }
+}
+
+fn float_bits(value: f32) -> u32 {
+    if value.is_nan() {
+        f32::NAN.to_bits()
+    } else if value == 0.0 {
+        0
+    } else {
+        value.to_bits()
+    }
+}
+
+fn write_len<H: Hasher>(state: &mut H, len: usize) {
+    state.write(&(len as u64).to_le_bytes());
+}
@@ -0,0 +1,23 @@
+//! Passes rewriting a [`Tree`](crate::Tree) into a new tree.
+//!
+//! Nodes and items produced by a pass keep the locations they were copied from, so errors in
+//! later stages still point into the original sources.
+pub use self::variables::{Variables, VariableError, VariableResult};
+pub use self::macros::{Macros, MacroError, MacroResult, Expansions};
+pub use self::conditionals::{Conditionals, ConditionError, ConditionResult};
+
+use crate::{Item, ItemKind};
+
+
+mod variables;
+mod macros;
+mod conditionals;
+
+/// The name following `sigil` in a sigil item, or in a word starting with `sigil` for trees
+/// parsed without sigils.
+fn sigil_name(item: &Item, sigil: char) -> Option<&str> {
+    match &item.kind {
+        ItemKind::Word(word) => word.strip_prefix(sigil),
+        kind => kind.sigil_name(sigil),
+    }
+}
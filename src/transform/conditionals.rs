@@ -0,0 +1,182 @@
+use std::collections::HashSet;
+
+use smol_str::SmolStr;
+use src_ctx::SourceError;
+
+use crate::{Tree, Node, NodeKind, Directive, Item};
+
+
+/// Type alias for [`Result`] with [`ConditionError`].
+pub type ConditionResult<T> = Result<T, SourceError<ConditionError>>;
+
+/// Errors encountered during [`Conditionals::apply`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConditionError {
+    #[error("Conditional is not a directive")]
+    ExpectedDirective,
+    #[error("Conditional has unexpected arguments")]
+    UnexpectedArguments,
+    #[error("Conditional without condition")]
+    MissingCondition,
+    #[error("Condition can only contain flag words")]
+    InvalidCondition,
+    #[error("`else` without preceding `if`")]
+    UnexpectedElse,
+}
+
+/// Conditional inclusion pass.
+///
+/// A directive like `if a !b:` is replaced by its children when the flag `a` is set and the
+/// flag `b` is not, and removed otherwise. It can be followed by `else if <flags>:` and `else:`
+/// siblings, of which only the first branch with a satisfied condition is included.
+#[derive(Debug, Clone)]
+pub struct Conditionals {
+    if_keyword: SmolStr,
+    else_keyword: SmolStr,
+    flags: HashSet<SmolStr>,
+}
+
+impl Default for Conditionals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Conditionals {
+    /// Create a pass without any set flags.
+    pub fn new() -> Self {
+        Self { if_keyword: "if".into(), else_keyword: "else".into(), flags: HashSet::new() }
+    }
+
+    /// Set the keywords used instead of `if` and `else`.
+    pub fn keywords(mut self, if_keyword: &str, else_keyword: &str) -> Self {
+        self.if_keyword = if_keyword.into();
+        self.else_keyword = else_keyword.into();
+        self
+    }
+
+    /// Set a flag.
+    pub fn flag(mut self, flag: &str) -> Self {
+        self.flags.insert(flag.into());
+        self
+    }
+
+    /// Set multiple flags.
+    pub fn flags<I>(mut self, flags: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<SmolStr>,
+    {
+        self.flags.extend(flags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Remove all nodes excluded by conditionals from a [`Tree`].
+    pub fn apply(&self, tree: &Tree) -> ConditionResult<Tree> {
+        let roots = self.nodes(tree)?;
+        Ok(Tree { roots })
+    }
+
+    fn nodes(&self, nodes: &[Node]) -> ConditionResult<Vec<Node>> {
+        let mut output = Vec::with_capacity(nodes.len());
+        let mut chain_taken = None;
+        for node in nodes {
+            let Some((branch, directive)) = self.branch(node)? else {
+                output.push(self.node(node)?);
+                chain_taken = None;
+                continue;
+            };
+            let taken = match (branch, chain_taken) {
+                (Branch::If(condition), _) => self.is_satisfied(condition)?,
+                (Branch::ElseIf(condition), Some(false)) => self.is_satisfied(condition)?,
+                (Branch::Else, Some(false)) => true,
+                (Branch::ElseIf(_) | Branch::Else, Some(true)) => false,
+                (Branch::ElseIf(_) | Branch::Else, None) => return Err(SourceError::new(
+                    ConditionError::UnexpectedElse,
+                    node.location,
+                    "unexpected else",
+                )),
+            };
+            if taken {
+                output.extend(self.nodes(&directive.children)?);
+            }
+            chain_taken = match branch {
+                Branch::If(_) => Some(taken),
+                Branch::ElseIf(_) => Some(taken || chain_taken == Some(true)),
+                Branch::Else => None,
+            };
+        }
+        Ok(output)
+    }
+
+    fn node(&self, node: &Node) -> ConditionResult<Node> {
+        let kind = match &node.kind {
+            NodeKind::Statement(statement) => NodeKind::Statement(statement.clone()),
+            NodeKind::Directive(directive) => NodeKind::Directive(Directive {
+                signature: directive.signature.clone(),
+                arguments: directive.arguments.clone(),
+                children: self.nodes(&directive.children)?,
+            }),
+        };
+        Ok(Node {
+            kind,
+            location: node.location,
+            docs: node.docs.clone(),
+            comment: node.comment.clone(),
+        })
+    }
+
+    fn branch<'a>(&self, node: &'a Node) -> ConditionResult<Option<(Branch<'a>, &'a Directive)>> {
+        let signature = match &node.kind {
+            NodeKind::Directive(directive) => &directive.signature,
+            NodeKind::Statement(statement) => &statement.signature,
+        };
+        let branch = match signature.as_slice() {
+            [keyword, condition @ ..] if keyword.word() == Some(&self.if_keyword) => {
+                Branch::If(condition)
+            },
+            [keyword, next, condition @ ..]
+                if keyword.word() == Some(&self.else_keyword)
+                    && next.word() == Some(&self.if_keyword) =>
+            {
+                Branch::ElseIf(condition)
+            },
+            [keyword] if keyword.word() == Some(&self.else_keyword) => Branch::Else,
+            _ => return Ok(None),
+        };
+        let error = |error, label| Err(SourceError::new(error, node.location, label));
+        let Some(directive) = node.directive() else {
+            return error(ConditionError::ExpectedDirective, "conditional statement");
+        };
+        if !directive.arguments.is_empty() {
+            return error(ConditionError::UnexpectedArguments, "conditional arguments");
+        }
+        if matches!(branch, Branch::If([]) | Branch::ElseIf([])) {
+            return error(ConditionError::MissingCondition, "missing condition");
+        }
+        Ok(Some((branch, directive)))
+    }
+
+    fn is_satisfied(&self, condition: &[Item]) -> ConditionResult<bool> {
+        let mut satisfied = true;
+        for item in condition {
+            let word = item.word_str().ok_or_else(|| SourceError::new(
+                ConditionError::InvalidCondition,
+                item.location.start(),
+                "invalid condition",
+            ))?;
+            satisfied &= match word.strip_prefix('!') {
+                Some(flag) => !self.flags.contains(flag),
+                None => self.flags.contains(word),
+            };
+        }
+        Ok(satisfied)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Branch<'a> {
+    If(&'a [Item]),
+    ElseIf(&'a [Item]),
+    Else,
+}
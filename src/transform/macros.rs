@@ -0,0 +1,455 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use smol_str::SmolStr;
+use src_ctx::{SourceError, Offset};
+
+use crate::{Tree, Node, NodeKind, Directive, Statement, Item, ItemKind};
+
+
+/// Type alias for [`Result`] with [`MacroError`].
+pub type MacroResult<T> = Result<T, SourceError<MacroError>>;
+
+/// Errors encountered during [`Macros::apply`].
+///
+/// Errors inside of expanded nodes carry the call sites of all active expansions as context,
+/// innermost first.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MacroError {
+    #[error("Invalid macro declaration")]
+    InvalidDeclaration,
+    #[error("Macro `{name}` is already declared")]
+    Duplicate { name: SmolStr },
+    #[error("Macro `{name}` expects {expected} arguments, found {found}")]
+    ArgumentCount { name: SmolStr, expected: usize, found: usize },
+    #[error("Macro expansion exceeded the recursion limit of {limit}")]
+    RecursionLimit { limit: usize },
+    #[error("Macro expansion exceeded the limit of {limit} expansions")]
+    ExpansionLimit { limit: usize },
+}
+
+/// Macro declaration and expansion pass.
+///
+/// A directive like `macro name a b:` declares the macro `name` with the parameters `a` and `b`
+/// for its following siblings and their descendants. Its children are the template.
+///
+/// Nodes starting with the word `name` are invocations. The items following the name in their
+/// signature and arguments are bound to the parameters in order, and the invocation is replaced
+/// by the template nodes with these substitutions:
+///
+/// * `$a` words are replaced by the item bound to the parameter `a`.
+/// * `$$tmp` words are replaced by `tmp#<n>`, where `n` is unique to the expansion and never
+///   follows a `#` in the words of the tree, so generated names cannot clash with written ones.
+/// * A `$children` statement is replaced by the children of the invocation, which are expanded
+///   where the macro is invoked, so macros declared in the template do not apply to them.
+///
+/// [`ItemKind::Sigil`] items with the same sigil are substituted like words. Only the values of
+/// pair items are substituted, their keys are kept.
+///
+/// Other words starting with `$` are kept, so variables can be substituted afterwards.
+#[derive(Debug, Clone)]
+pub struct Macros {
+    keyword: SmolStr,
+    sigil: char,
+    recursion_limit: usize,
+    expansion_limit: usize,
+}
+
+impl Default for Macros {
+    fn default() -> Self {
+        Self::new("macro")
+    }
+}
+
+impl Macros {
+    /// Create a pass for declarations using the given keyword.
+    pub fn new(keyword: &str) -> Self {
+        Self {
+            keyword: keyword.into(),
+            sigil: '$',
+            recursion_limit: 32,
+            expansion_limit: 10_000,
+        }
+    }
+
+    /// Set the character marking parameter uses. Defaults to `$`.
+    pub fn sigil(mut self, sigil: char) -> Self {
+        self.sigil = sigil;
+        self
+    }
+
+    /// Set the maximum depth of nested expansions. Defaults to 32.
+    pub fn recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// Set the maximum total number of expansions. Defaults to 10000.
+    pub fn expansion_limit(mut self, limit: usize) -> Self {
+        self.expansion_limit = limit;
+        self
+    }
+
+    /// Expand all macros in a [`Tree`].
+    pub fn apply(&self, tree: &Tree) -> MacroResult<Tree> {
+        self.apply_traced(tree).map(|(tree, _)| tree)
+    }
+
+    /// Expand all macros in a [`Tree`], recording the call sites of the expanded nodes.
+    pub fn apply_traced(&self, tree: &Tree) -> MacroResult<(Tree, Expansions)> {
+        let mut reserved = HashSet::new();
+        reserve_nodes(tree, &mut reserved);
+        let mut expander = Expander {
+            config: self,
+            scope: Vec::new(),
+            calls: Vec::new(),
+            expansions: 0,
+            generated: 0,
+            reserved,
+            trace: Expansions::default(),
+        };
+        let roots = expander.nodes(tree)?;
+        Ok((Tree { roots }, expander.trace))
+    }
+}
+
+/// The call sites of macro expansions, recorded by [`Macros::apply_traced`].
+///
+/// Expanded nodes and items keep the locations of their template, so errors found by later
+/// passes point into the template. The call sites can be added to these errors as context.
+#[derive(Debug, Clone, Default)]
+pub struct Expansions {
+    call_sites: HashMap<Offset, Vec<Offset>>,
+}
+
+impl Expansions {
+    /// The call sites of the expansions copying the template node or item at `location`,
+    /// innermost first.
+    ///
+    /// Templates expanded more than once have the call sites of all of their expansions.
+    pub fn call_sites(&self, location: Offset) -> &[Offset] {
+        self.call_sites.get(&location).map_or(&[][..], |call_sites| call_sites.as_slice())
+    }
+
+    /// Add the call sites of the location of `error` as context.
+    pub fn with_context<E>(&self, error: SourceError<E>) -> SourceError<E> {
+        self.call_sites(error.offset()).iter()
+            .fold(error, |error, call_site| error.with_context(*call_site))
+    }
+
+    fn record(&mut self, location: Offset, stack: &[Offset]) {
+        let call_sites = self.call_sites.entry(location).or_default();
+        for call_site in stack {
+            if !call_sites.contains(call_site) {
+                call_sites.push(*call_site);
+            }
+        }
+    }
+
+    fn record_nodes(&mut self, nodes: &[Node], stack: &[Offset]) {
+        for node in nodes {
+            self.record(node.location, stack);
+            match &node.kind {
+                NodeKind::Statement(statement) => self.record_items(&statement.signature, stack),
+                NodeKind::Directive(directive) => {
+                    self.record_items(&directive.signature, stack);
+                    self.record_items(&directive.arguments, stack);
+                    self.record_nodes(&directive.children, stack);
+                },
+            }
+        }
+    }
+
+    fn record_items(&mut self, items: &[Item], stack: &[Offset]) {
+        for item in items {
+            self.record(item.location.start(), stack);
+            match &item.kind {
+                ItemKind::Parentheses(items)
+                | ItemKind::Brackets(items)
+                | ItemKind::Braces(items) => self.record_items(items, stack),
+                ItemKind::Pair { key, value, .. } => {
+                    self.record_items(std::slice::from_ref(key), stack);
+                    self.record_items(std::slice::from_ref(value), stack);
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+struct Definition {
+    name: SmolStr,
+    parameters: Vec<SmolStr>,
+    template: Vec<Node>,
+}
+
+struct Expander<'a> {
+    config: &'a Macros,
+    scope: Vec<Rc<Definition>>,
+    calls: Vec<Offset>,
+    expansions: usize,
+    /// The number of the last generated names, skipping the `reserved` ones.
+    generated: usize,
+    reserved: HashSet<usize>,
+    trace: Expansions,
+}
+
+impl Expander<'_> {
+    fn error(
+        &self,
+        error: MacroError,
+        location: Offset,
+        label: &'static str,
+    ) -> SourceError<MacroError> {
+        let error = SourceError::new(error, location, label);
+        self.calls.iter().rev().fold(error, |error, call| error.with_context(*call))
+    }
+
+    fn nodes(&mut self, nodes: &[Node]) -> MacroResult<Vec<Node>> {
+        let outer = self.scope.len();
+        let mut output = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            if let Some(definition) = self.declaration(node)? {
+                self.scope.push(Rc::new(definition));
+            } else if let Some(definition) = self.invoked(node) {
+                output.extend(self.expand(node, &definition)?);
+            } else {
+                output.push(self.node(node)?);
+            }
+        }
+        self.scope.truncate(outer);
+        Ok(output)
+    }
+
+    fn node(&mut self, node: &Node) -> MacroResult<Node> {
+        let kind = match &node.kind {
+            NodeKind::Statement(statement) => NodeKind::Statement(statement.clone()),
+            NodeKind::Directive(directive) => NodeKind::Directive(Directive {
+                signature: directive.signature.clone(),
+                arguments: directive.arguments.clone(),
+                children: self.nodes(&directive.children)?,
+            }),
+        };
+        Ok(Node {
+            kind,
+            location: node.location,
+            docs: node.docs.clone(),
+            comment: node.comment.clone(),
+        })
+    }
+
+    fn declaration(&self, node: &Node) -> MacroResult<Option<Definition>> {
+        if signature(node).first().and_then(|item| item.word()) != Some(&self.config.keyword) {
+            return Ok(None);
+        }
+        let invalid = || {
+            self.error(MacroError::InvalidDeclaration, node.location, "invalid declaration")
+        };
+        let directive = node.directive().ok_or_else(invalid)?;
+        let mut words = directive.signature[1..].iter().map(|item| item.word().cloned());
+        let name = words.next().flatten().ok_or_else(invalid)?;
+        let parameters = words.collect::<Option<Vec<_>>>().ok_or_else(invalid)?;
+        if name.starts_with(self.config.sigil) || !directive.arguments.is_empty() {
+            return Err(invalid());
+        }
+        if self.scope.iter().any(|definition| definition.name == name) {
+            let error = MacroError::Duplicate { name };
+            return Err(self.error(error, node.location, "duplicate declaration"));
+        }
+        Ok(Some(Definition { name, parameters, template: directive.children.clone() }))
+    }
+
+    fn invoked(&self, node: &Node) -> Option<Rc<Definition>> {
+        let name = signature(node).first()?.word()?;
+        self.scope.iter().rev().find(|definition| &definition.name == name).cloned()
+    }
+
+    fn expand(&mut self, node: &Node, definition: &Definition) -> MacroResult<Vec<Node>> {
+        let (arguments, children) = match &node.kind {
+            NodeKind::Statement(statement) => (statement.signature[1..].to_vec(), &[][..]),
+            NodeKind::Directive(directive) => {
+                let mut arguments = directive.signature[1..].to_vec();
+                arguments.extend(directive.arguments.iter().cloned());
+                (arguments, &directive.children[..])
+            },
+        };
+        if arguments.len() != definition.parameters.len() {
+            let error = MacroError::ArgumentCount {
+                name: definition.name.clone(),
+                expected: definition.parameters.len(),
+                found: arguments.len(),
+            };
+            return Err(self.error(error, node.location, "invocation"));
+        }
+        if self.calls.len() >= self.config.recursion_limit {
+            let error = MacroError::RecursionLimit { limit: self.config.recursion_limit };
+            return Err(self.error(error, node.location, "invocation"));
+        }
+        if self.expansions >= self.config.expansion_limit {
+            let error = MacroError::ExpansionLimit { limit: self.config.expansion_limit };
+            return Err(self.error(error, node.location, "invocation"));
+        }
+        let children = self.nodes(children)?;
+        self.expansions += 1;
+        self.generated += 1;
+        while self.reserved.contains(&self.generated) {
+            self.generated += 1;
+        }
+        let substitution = Substitution {
+            sigil: self.config.sigil,
+            parameters: &definition.parameters,
+            arguments: &arguments,
+            expansion: self.generated,
+        };
+        let expanded = substitution.nodes(&definition.template);
+        let stack: Vec<_> = std::iter::once(node.location)
+            .chain(self.calls.iter().rev().copied())
+            .collect();
+        self.trace.record_nodes(&definition.template, &stack);
+        self.calls.push(node.location);
+        let result = self.nodes(&expanded);
+        self.calls.pop();
+        Ok(self.splice_children(result?, &children))
+    }
+
+    /// Replace the `$children` statements kept in expanded template nodes with `children`.
+    fn splice_children(&self, nodes: Vec<Node>, children: &[Node]) -> Vec<Node> {
+        let mut output = Vec::with_capacity(nodes.len());
+        for mut node in nodes {
+            match &mut node.kind {
+                NodeKind::Statement(statement)
+                    if is_children(&statement.signature, self.config.sigil) =>
+                {
+                    output.extend(children.iter().cloned());
+                    continue;
+                },
+                NodeKind::Statement(_) => {},
+                NodeKind::Directive(directive) => {
+                    let nested = std::mem::take(&mut directive.children);
+                    directive.children = self.splice_children(nested, children);
+                },
+            }
+            output.push(node);
+        }
+        output
+    }
+}
+
+struct Substitution<'a> {
+    sigil: char,
+    parameters: &'a [SmolStr],
+    arguments: &'a [Item],
+    expansion: usize,
+}
+
+impl Substitution<'_> {
+    fn nodes(&self, nodes: &[Node]) -> Vec<Node> {
+        let mut output = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let kind = match &node.kind {
+                // Kept until the expansion is done, see `Expander::splice_children`.
+                NodeKind::Statement(statement) if is_children(&statement.signature, self.sigil) => {
+                    output.push(node.clone());
+                    continue;
+                },
+                NodeKind::Statement(statement) => NodeKind::Statement(Statement {
+                    signature: self.items(&statement.signature),
+                }),
+                NodeKind::Directive(directive) => NodeKind::Directive(Directive {
+                    signature: self.items(&directive.signature),
+                    arguments: self.items(&directive.arguments),
+                    children: self.nodes(&directive.children),
+                }),
+            };
+            output.push(Node {
+                kind,
+                location: node.location,
+                docs: node.docs.clone(),
+                comment: node.comment.clone(),
+            });
+        }
+        output
+    }
+
+    fn items(&self, items: &[Item]) -> Vec<Item> {
+        items.iter().map(|item| self.item(item)).collect()
+    }
+
+    fn item(&self, item: &Item) -> Item {
+        let kind = match &item.kind {
+            ItemKind::Word(_) | ItemKind::Sigil { .. } => return self.word(item),
+            ItemKind::Parentheses(items) => ItemKind::Parentheses(self.items(items)),
+            ItemKind::Brackets(items) => ItemKind::Brackets(self.items(items)),
+            ItemKind::Braces(items) => ItemKind::Braces(self.items(items)),
+            ItemKind::Pair { key, separator, value } => ItemKind::Pair {
+                key: key.clone(),
+                separator: *separator,
+                value: Box::new(self.item(value)),
+            },
+            ItemKind::Int(_) | ItemKind::Float(_) | ItemKind::Bool(_) | ItemKind::Null => {
+                item.kind.clone()
+            },
+        };
+        Item { kind, location: item.location }
+    }
+
+    fn word(&self, item: &Item) -> Item {
+        let Some(name) = super::sigil_name(item, self.sigil) else {
+            return item.clone();
+        };
+        if let Some(generated) = name.strip_prefix(self.sigil).filter(|name| !name.is_empty()) {
+            let kind = ItemKind::Word(format!("{}#{}", generated, self.expansion).into());
+            return Item { kind, location: item.location };
+        }
+        match self.parameters.iter().position(|parameter| parameter == name) {
+            Some(index) => self.arguments[index].clone(),
+            None => item.clone(),
+        }
+    }
+}
+
+fn is_children(signature: &[Item], sigil: char) -> bool {
+    match signature {
+        [item] => super::sigil_name(item, sigil) == Some("children"),
+        _ => false,
+    }
+}
+
+fn signature(node: &Node) -> &[Item] {
+    match &node.kind {
+        NodeKind::Directive(directive) => &directive.signature,
+        NodeKind::Statement(statement) => &statement.signature,
+    }
+}
+
+/// Collect the numbers following the last `#` of words and sigil names.
+fn reserve_nodes(nodes: &[Node], reserved: &mut HashSet<usize>) {
+    for node in nodes {
+        reserve_items(signature(node), reserved);
+        if let NodeKind::Directive(directive) = &node.kind {
+            reserve_items(&directive.arguments, reserved);
+            reserve_nodes(&directive.children, reserved);
+        }
+    }
+}
+
+fn reserve_items(items: &[Item], reserved: &mut HashSet<usize>) {
+    for item in items {
+        let name = match &item.kind {
+            ItemKind::Word(name) | ItemKind::Sigil { name, .. } => name,
+            ItemKind::Parentheses(items) | ItemKind::Brackets(items) | ItemKind::Braces(items) => {
+                reserve_items(items, reserved);
+                continue;
+            },
+            ItemKind::Pair { key, value, .. } => {
+                reserve_items(std::slice::from_ref(key), reserved);
+                reserve_items(std::slice::from_ref(value), reserved);
+                continue;
+            },
+            ItemKind::Int(_) | ItemKind::Float(_) | ItemKind::Bool(_) | ItemKind::Null => continue,
+        };
+        if let Some(number) = name.rsplit_once('#').and_then(|(_, number)| number.parse().ok()) {
+            reserved.insert(number);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use smol_str::SmolStr;
+use src_ctx::{SourceError, Offset};
+
+use crate::{Tree, Node, NodeKind, Directive, Statement, Item, ItemKind};
+
+
+/// Type alias for [`Result`] with [`VariableError`].
+pub type VariableResult<T> = Result<T, SourceError<VariableError>>;
+
+/// Errors encountered during [`Variables::apply`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum VariableError {
+    #[error("Undefined variable `{name}`")]
+    Undefined { name: SmolStr },
+    #[error("Variable `{name}` shadows an earlier definition")]
+    Shadowed { name: SmolStr },
+    #[error("Invalid variable definition")]
+    InvalidDefinition,
+    #[error("Pair value is not a single item")]
+    InvalidPairValue,
+}
+
+/// Variable definition and substitution pass.
+///
+/// A directive like `let name: 1 2` defines the variable `name` for its following siblings
+/// and their descendants. Every `$name` word or [`ItemKind::Sigil`] item in their signatures and
+/// arguments is replaced by the defined items. Definitions are removed from the resulting tree.
+///
+/// The value of a pair item can only be replaced by a single item, while its key is kept.
+#[derive(Debug, Clone)]
+pub struct Variables {
+    keyword: SmolStr,
+    sigil: char,
+    allow_shadowing: bool,
+}
+
+impl Default for Variables {
+    fn default() -> Self {
+        Self::new("let")
+    }
+}
+
+impl Variables {
+    /// Create a pass for definitions using the given keyword.
+    pub fn new(keyword: &str) -> Self {
+        Self { keyword: keyword.into(), sigil: '$', allow_shadowing: false }
+    }
+
+    /// Set the character marking variable uses. Defaults to `$`.
+    pub fn sigil(mut self, sigil: char) -> Self {
+        self.sigil = sigil;
+        self
+    }
+
+    /// Allow redefining variables that are already in scope.
+    pub fn allow_shadowing(mut self, allow: bool) -> Self {
+        self.allow_shadowing = allow;
+        self
+    }
+
+    /// Substitute all variables in a [`Tree`].
+    pub fn apply(&self, tree: &Tree) -> VariableResult<Tree> {
+        let roots = self.nodes(tree, &mut Vec::new())?;
+        Ok(Tree { roots })
+    }
+
+    fn nodes(&self, nodes: &[Node], scope: &mut Vec<Binding>) -> VariableResult<Vec<Node>> {
+        let outer = scope.len();
+        let mut output = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            if let Some(binding) = self.definition(node, scope)? {
+                scope.push(binding);
+            } else {
+                output.push(self.node(node, scope)?);
+            }
+        }
+        scope.truncate(outer);
+        Ok(output)
+    }
+
+    fn node(&self, node: &Node, scope: &mut Vec<Binding>) -> VariableResult<Node> {
+        let kind = match &node.kind {
+            NodeKind::Statement(statement) => NodeKind::Statement(Statement {
+                signature: self.items(&statement.signature, scope)?,
+            }),
+            NodeKind::Directive(directive) => NodeKind::Directive(Directive {
+                signature: self.items(&directive.signature, scope)?,
+                arguments: self.items(&directive.arguments, scope)?,
+                children: self.nodes(&directive.children, scope)?,
+            }),
+        };
+        Ok(Node {
+            kind,
+            location: node.location,
+            docs: node.docs.clone(),
+            comment: node.comment.clone(),
+        })
+    }
+
+    fn definition(&self, node: &Node, scope: &[Binding]) -> VariableResult<Option<Binding>> {
+        let signature = match &node.kind {
+            NodeKind::Directive(directive) => &directive.signature,
+            NodeKind::Statement(statement) => &statement.signature,
+        };
+        if signature.first().and_then(|item| item.word()) != Some(&self.keyword) {
+            return Ok(None);
+        }
+        let invalid = || SourceError::new(
+            VariableError::InvalidDefinition,
+            node.location,
+            "invalid definition",
+        );
+        let directive = node.directive().ok_or_else(invalid)?;
+        let name = match &directive.signature[..] {
+            [_, name] => name.word().ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+        if directive.arguments.is_empty() || !directive.children.is_empty() {
+            return Err(invalid());
+        }
+        if !self.allow_shadowing {
+            if let Some(previous) = scope.iter().find(|binding| &binding.name == name) {
+                let error = SourceError::new(
+                    VariableError::Shadowed { name: name.clone() },
+                    node.location,
+                    "shadowing definition",
+                );
+                return Err(error.with_context(previous.location));
+            }
+        }
+        Ok(Some(Binding {
+            name: name.clone(),
+            items: self.items(&directive.arguments, scope)?,
+            location: node.location,
+        }))
+    }
+
+    fn items(&self, items: &[Item], scope: &[Binding]) -> VariableResult<Vec<Item>> {
+        let mut output = Vec::with_capacity(items.len());
+        for item in items {
+            let kind = match &item.kind {
+                ItemKind::Word(_) | ItemKind::Sigil { .. } => match self.variable_name(item) {
+                    Some(name) => {
+                        let binding = scope.iter().rev()
+                            .find(|binding| binding.name == name)
+                            .ok_or_else(|| SourceError::new(
+                                VariableError::Undefined { name: name.into() },
+                                item.location.start(),
+                                "undefined variable",
+                            ))?;
+                        output.extend(binding.items.iter().cloned());
+                        continue;
+                    },
+                    None => item.kind.clone(),
+                },
+                ItemKind::Parentheses(items) => ItemKind::Parentheses(self.items(items, scope)?),
+                ItemKind::Brackets(items) => ItemKind::Brackets(self.items(items, scope)?),
+                ItemKind::Braces(items) => ItemKind::Braces(self.items(items, scope)?),
+                ItemKind::Pair { key, separator, value } => ItemKind::Pair {
+                    key: key.clone(),
+                    separator: *separator,
+                    value: Box::new(self.pair_value(value, scope)?),
+                },
+                ItemKind::Int(_) | ItemKind::Float(_) | ItemKind::Bool(_) | ItemKind::Null => {
+                    item.kind.clone()
+                },
+            };
+            output.push(Item { kind, location: item.location });
+        }
+        Ok(output)
+    }
+
+    fn pair_value(&self, value: &Item, scope: &[Binding]) -> VariableResult<Item> {
+        let mut items = self.items(std::slice::from_ref(value), scope)?;
+        if items.len() != 1 {
+            return Err(SourceError::new(
+                VariableError::InvalidPairValue,
+                value.location.start(),
+                "pair value",
+            ));
+        }
+        Ok(items.remove(0))
+    }
+
+    fn variable_name<'a>(&self, item: &'a Item) -> Option<&'a str> {
+        super::sigil_name(item, self.sigil).filter(|name| !name.is_empty())
+    }
+}
+
+struct Binding {
+    name: SmolStr,
+    items: Vec<Item>,
+    location: Offset,
+}
@@ -0,0 +1,65 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree};
+use treelang::arena::{TreeArena, ArenaNodeKind};
+use assert_matches::assert_matches;
+
+
+fn parse(content: &str) -> (Tree, SourceMap) {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    (tree, map)
+}
+
+#[test]
+fn navigation() {
+    let (tree, map) = parse(&normalize("
+        |abc:
+        |  def: 23
+        |    ghi
+        |  jkl
+        |mno
+    "));
+    let arena = TreeArena::new(&tree);
+    assert_eq!(arena.len(), 5);
+    let ids: Vec<_> = arena.ids().collect();
+    let [abc, def, ghi, jkl, mno] = ids[..] else { panic!("expected five nodes") };
+
+    assert_eq!(arena.roots(), [abc, mno]);
+    assert_eq!(arena.children(abc), [def, jkl]);
+    assert_eq!(arena.parent(ghi), Some(def));
+    assert_eq!(arena.parent(abc), None);
+    assert_eq!(arena.ancestors(ghi).collect::<Vec<_>>(), [def, abc]);
+    assert_eq!(arena.depth(ghi), 2);
+    assert_eq!(arena.depth(mno), 0);
+    assert_eq!(arena.siblings(jkl), [def, jkl]);
+    assert_eq!(arena.next_sibling(def), Some(jkl));
+    assert_eq!(arena.next_sibling(jkl), None);
+    assert_eq!(arena.previous_sibling(mno), Some(abc));
+    assert_eq!(arena.previous_sibling(abc), None);
+
+    assert!(arena[def].is_directive());
+    assert_eq!(map.span_str(arena[ghi].signature()[0].location), "ghi");
+    assert_matches!(&arena[def].kind, ArenaNodeKind::Directive { arguments, .. } => {
+        assert_eq!(arguments[0].int(), Some(23));
+    });
+    assert_eq!(arena[jkl].location, tree[0].children()[1].location);
+    assert_eq!(def.index(), 1);
+}
+
+#[test]
+fn conversion() {
+    let (tree, _) = parse("abc:\n  def: 1\n    ghi\njkl [2]");
+    let arena = TreeArena::from(&tree);
+    assert_eq!(arena.to_tree(), tree);
+    assert_eq!(arena.to_node(arena.children(arena.roots()[0])[0]), tree[0].children()[0]);
+
+    let mut depths = vec![0; arena.len()];
+    for id in arena.ids() {
+        depths[id.index()] = arena.depth(id);
+    }
+    assert_eq!(depths, [0, 1, 2, 0]);
+
+    assert!(TreeArena::new(&Tree::default()).is_empty());
+}
@@ -0,0 +1,94 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, ParseError, ParseOptions, Literals, borrowed};
+use assert_matches::assert_matches;
+
+
+fn assert_same_as_owned(content: &str) -> Result<Tree, ParseError> {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), normalize(content).into())
+        .try_into_inserted().unwrap();
+    let borrowed = borrowed::Tree::parse(map.input(index), Indent::spaces(2))
+        .map(borrowed::Tree::into_owned)
+        .map_err(|error| error.into_error());
+    let owned = Tree::parse(map.input(index), Indent::spaces(2))
+        .map_err(|error| error.into_error());
+    assert_eq!(borrowed, owned);
+    owned
+}
+
+#[test]
+fn words_are_borrowed() {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), "abc: def 23\n  ghi (j 2.5)".into())
+        .try_into_inserted().unwrap();
+    let tree = borrowed::Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    assert_eq!(tree.len(), 1);
+
+    let directive = tree[0].directive().unwrap();
+    assert_matches!(directive.signature[..], [borrowed::Item {
+        kind: borrowed::ItemKind::Word("abc"),
+        ..
+    }]);
+    assert_matches!(directive.arguments[..], [
+        borrowed::Item { kind: borrowed::ItemKind::Word("def"), .. },
+        borrowed::Item { kind: borrowed::ItemKind::Int(23), .. },
+    ]);
+    let argument = &directive.arguments[0];
+    assert_eq!(argument.word(), Some(map.span_str(argument.location)));
+
+    let statement = directive.children[0].statement().unwrap();
+    assert_matches!(&statement.signature[1].kind, borrowed::ItemKind::Parentheses(items) => {
+        assert_eq!(items[0].word(), Some("j"));
+        assert_matches!(items[1].kind, borrowed::ItemKind::Float(value) if value == 2.5);
+    });
+}
+
+#[test]
+fn into_owned() {
+    let tree = assert_same_as_owned("
+        |abc: def [1 {2 x}] -3
+        |  ghi
+        |  jkl: 2.5
+        |    mno (p q)
+        |rst
+    ").unwrap();
+    assert_eq!(tree.len(), 2);
+}
+
+#[test]
+fn errors() {
+    let error = assert_same_as_owned("
+        |abc
+        |  def
+    ").unwrap_err();
+    assert_eq!(error, ParseError::StatementWithChild);
+
+    let error = assert_same_as_owned("abc [1").unwrap_err();
+    assert_eq!(error, ParseError::UnclosedGroup { missing: ']' });
+}
+
+#[test]
+fn parse_options() {
+    let mut map = SourceMap::new();
+    let content = "server: port=80 $host true ; primary\n  tls null";
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2))
+        .pair_separator('=')
+        .sigils(&['$'])
+        .literals(Literals::DEFAULT)
+        .trailing_comments(true);
+    let tree = borrowed::Tree::parse_with(map.input(index), options).unwrap();
+
+    let directive = tree[0].directive().unwrap();
+    let (key, value) = directive.arguments[0].pair().unwrap();
+    assert_eq!(key.word(), Some("port"));
+    assert_eq!(value.int(), Some(80));
+    assert!(directive.arguments[1].is_sigil());
+    assert_eq!(directive.arguments[2].bool(), Some(true));
+    assert_eq!(tree[0].comment.as_ref().map(|comment| comment.text), Some("primary"));
+    assert!(directive.children[0].statement().unwrap().signature[1].is_null());
+
+    let owned = Tree::parse_with(map.input(index), options).unwrap();
+    assert_eq!(tree.into_owned(), owned);
+}
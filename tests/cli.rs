@@ -0,0 +1,104 @@
+#![cfg(feature = "cli")]
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("treelang-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn run(args: &[&str], path: &PathBuf) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_treelang"))
+        .args(args)
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn check() {
+    let valid = temp_file("check-valid.tl", "abc:\n  def 23\n");
+    assert!(run(&["check"], &valid).status.success());
+
+    let invalid = temp_file("check-invalid.tl", "abc:\n  def (23\n");
+    let output = run(&["check"], &invalid);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Missing closing `)` character"));
+    assert!(stderr.contains("check-invalid.tl:2:7"));
+    assert!(stderr.contains("2 |   def (23\n  |       ^"));
+
+    let nested = temp_file("check-nested.tl", "abc\n  def\n");
+    let stderr = String::from_utf8(run(&["check"], &nested).stderr).unwrap();
+    assert!(stderr.contains("--> "));
+    assert!(stderr.contains("check-nested.tl:2:3"));
+    assert!(stderr.contains("2 |   def\n  |   ^\n"));
+    assert!(stderr.contains("::: "));
+    assert!(stderr.contains("check-nested.tl:1:1"));
+    assert!(stderr.contains("1 | abc\n  | ^ related\n"));
+
+    let output = run(&["--indent", "4", "check"], &valid);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run(&["unknown"], &valid);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn format() {
+    let path = temp_file("fmt.tl", "abc  def:  23\n    (ghi   1.5)\n__END__\nkept   as is\n");
+
+    let output = run(&["--indent", "4", "fmt", "--check"], &path);
+    assert_eq!(output.status.code(), Some(1));
+
+    assert!(run(&["--indent", "4", "fmt"], &path).status.success());
+    let formatted = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(formatted, "abc def: 23\n    (ghi 1.5)\n__END__\nkept   as is\n");
+    assert!(run(&["--indent", "4", "fmt", "--check"], &path).status.success());
+
+    let commented = temp_file("fmt-commented.tl", "abc ; comment\n");
+    let output = run(&["fmt"], &commented);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(std::fs::read_to_string(&commented).unwrap(), "abc ; comment\n");
+
+    let documented = temp_file("fmt-documented.tl", ";; Doc.\nabc  def\n");
+    assert!(run(&["fmt"], &documented).status.success());
+    assert_eq!(std::fs::read_to_string(&documented).unwrap(), ";; Doc.\nabc def\n");
+
+    let dangling = temp_file("fmt-dangling.tl", "abc  def\n;; Dangling.\n");
+    assert_eq!(run(&["fmt"], &dangling).status.code(), Some(1));
+}
+
+#[test]
+fn dump() {
+    let path = temp_file("dump.tl", "abc: (23)\n  def\n");
+
+    let output = run(&["dump"], &path);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with("\
+  directive @1:1
+    signature
+      word abc @1:1
+    arguments
+      parentheses @1:6
+        int 23 @1:7
+    children
+      statement @2:3
+        signature
+          word def @2:3
+"));
+
+    let output = run(&["dump", "--json"], &path);
+    assert!(output.status.success());
+    let dump: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let node = &dump["nodes"][0];
+    assert_eq!(node["kind"], "directive");
+    assert_eq!(node["arguments"][0]["value"][0]["value"], 23);
+    assert_eq!(node["children"][0]["signature"][0]["value"], "def");
+    assert_eq!(node["children"][0]["line"], 2);
+}
@@ -0,0 +1,248 @@
+#![cfg(feature = "json")]
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, ParseOptions, ParseError, Literals};
+use treelang::convert::{self, Converted, ConvertError, ConvertErrorKind, LossKind};
+use serde_json::{json, Value};
+use assert_matches::assert_matches;
+
+
+fn parse(content: &str) -> Tree {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    Tree::parse(map.input(index), Indent::spaces(2)).unwrap()
+}
+
+fn from_json(value: &Value, options: ParseOptions) -> Result<Converted<Tree>, ConvertError> {
+    convert::from_json(value, options, &mut SourceMap::new(), Origin::from_named("converted"))
+}
+
+#[test]
+fn tree_to_json() {
+    let tree = parse(&normalize("
+        |abc def: 23 -0.5
+        |  ghi (jkl [1] {})
+    "));
+    let converted = convert::to_json(&tree);
+    assert!(converted.is_lossless());
+    assert_eq!(converted.value, json!([
+        {
+            "directive": ["abc", "def"],
+            "arguments": [23, -0.5],
+            "children": [
+                { "statement": ["ghi", { "parentheses": ["jkl", { "brackets": [1] }, { "braces": [] }] }] },
+            ],
+        },
+    ]));
+
+    let tree = parse("test 0.1");
+    assert_eq!(convert::to_json(&tree).value, json!([{ "statement": ["test", 0.1] }]));
+}
+
+#[test]
+fn json_to_source() {
+    let value = json!([
+        { "directive": ["abc"], "children": [{ "statement": ["def", 2.0, { "braces": ["x"] }] }] },
+        { "directive": ["ghi", -3], "arguments": [0.1] },
+    ]);
+    let converted = from_json(&value, Indent::spaces(2).into()).unwrap();
+    assert!(converted.is_lossless());
+    assert_eq!(converted.value.to_source(Indent::spaces(2)), "abc:\n  def 2.0 {x}\nghi -3: 0.1\n");
+    assert_eq!(convert::to_json(&converted.value).value, json!([
+        { "directive": ["abc"], "arguments": [], "children": [
+            { "statement": ["def", 2.0, { "braces": ["x"] }] },
+        ] },
+        { "directive": ["ghi", -3], "arguments": [0.1], "children": [] },
+    ]));
+}
+
+#[test]
+fn losses() {
+    let value = json!([{ "statement": ["abc", 0.1234567891234], "note": "x", "comment": "y" }]);
+    let converted = from_json(&value, Indent::tabs().into()).unwrap();
+    assert_matches!(&converted.losses[..], [first, second, third] => {
+        assert_eq!(first.path, "/0/statement/1");
+        assert_matches!(first.kind, LossKind::FloatPrecision { .. });
+        assert_eq!(second.path, "/0/comment");
+        assert_eq!(second.kind, LossKind::CommentDropped);
+        assert_eq!(third.path, "/0/note");
+        assert_matches!(&third.kind, LossKind::UnknownKey { key } => assert_eq!(key, "note"));
+    });
+
+    let converted = convert::to_json(&parse("test 1.0"));
+    assert!(converted.is_lossless());
+}
+
+#[test]
+fn errors() {
+    let to_source = |value| from_json(&value, Indent::tabs().into()).map(|_| ());
+    assert_matches!(to_source(json!({})), Err(error) => {
+        assert_eq!(error.path, "");
+        assert_eq!(error.kind, ConvertErrorKind::ExpectedArray);
+    });
+    assert_matches!(to_source(json!([{ "statement": [] }])), Err(error) => {
+        assert_eq!(error.path, "/0/statement");
+        assert_eq!(error.kind, ConvertErrorKind::EmptySignature);
+    });
+    assert_matches!(to_source(json!([{ "statement": ["a b"] }])), Err(error) => {
+        assert_eq!(error.path, "/0/statement/0");
+        assert_matches!(error.kind, ConvertErrorKind::InvalidWord { .. });
+    });
+    for word in ["", "23", "-a", "a;b", "a:b", "(a"] {
+        assert_matches!(
+            to_source(json!([{ "statement": [word] }])),
+            Err(error) => assert_matches!(error.kind, ConvertErrorKind::InvalidWord { .. })
+        );
+    }
+    assert_matches!(to_source(json!([{ "statement": ["a", 3000000000_u64] }])), Err(error) => {
+        assert_matches!(error.kind, ConvertErrorKind::IntOutOfRange { .. });
+    });
+    assert_matches!(to_source(json!([{ "statement": ["a", 1e40] }])), Err(error) => {
+        assert_eq!(error.path, "/0/statement/1");
+        assert_eq!(error.kind, ConvertErrorKind::FloatOutOfRange { value: 1e40 });
+    });
+    let options = ParseOptions::new(Indent::tabs()).max_items(1);
+    let error = from_json(&json!([{ "statement": ["a", "b"] }]), options).unwrap_err();
+    assert_eq!(error.kind, ConvertErrorKind::Parse { error: ParseError::ItemLimit { limit: 1 } });
+    assert_matches!(to_source(json!([{ "statement": ["a", []] }])), Err(error) => {
+        assert_eq!(error.kind, ConvertErrorKind::ExpectedItem);
+    });
+    assert_matches!(to_source(json!([{ "statement": ["a"], "directive": ["b"] }])), Err(error) => {
+        assert_eq!(error.kind, ConvertErrorKind::AmbiguousNode);
+    });
+    assert_matches!(to_source(json!([{ "statement": [{ "braces": [], "brackets": [] }] }])), Err(error) => {
+        assert_eq!(error.kind, ConvertErrorKind::AmbiguousGroup);
+    });
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml() {
+    let tree = parse("abc: (def 23)");
+    let converted = convert::to_yaml(&tree);
+    let mut map = SourceMap::new();
+    let origin = Origin::from_named("converted");
+    let tree = convert::from_yaml(&converted.value, Indent::spaces(2).into(), &mut map, origin)
+        .unwrap();
+    assert_eq!(tree.value.to_source(Indent::spaces(2)), "abc: (def 23)\n");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml() {
+    let tree = parse("abc: (def 23)\n  ghi");
+    let converted = convert::to_toml(&tree).unwrap();
+    let mut map = SourceMap::new();
+    let origin = Origin::from_named("converted");
+    let tree = convert::from_toml(&converted.value, Indent::spaces(2).into(), &mut map, origin)
+        .unwrap();
+    assert_eq!(tree.value.to_source(Indent::spaces(2)), "abc: (def 23)\n  ghi\n");
+}
+
+#[test]
+fn literals() {
+    let value = json!([{ "statement": ["a", true, false, null] }]);
+    let converted = from_json(&value, Indent::spaces(2).into()).unwrap();
+    assert_eq!(converted.value.to_source(Indent::spaces(2)), "a true false null\n");
+    let paths: Vec<_> = converted.losses.iter().map(|loss| loss.path.as_str()).collect();
+    assert_eq!(paths, ["/0/statement/1", "/0/statement/2", "/0/statement/3"]);
+    assert_matches!(&converted.losses[0].kind, LossKind::Reinterpreted { word } => {
+        assert_eq!(word, "true");
+    });
+
+    let options = ParseOptions::new(Indent::spaces(2)).literals(Literals::DEFAULT);
+    let converted = from_json(&value, options).unwrap();
+    assert!(converted.is_lossless());
+    assert_eq!(convert::to_json(&converted.value).value, value);
+
+    let options = ParseOptions::new(Indent::spaces(2))
+        .literals(Literals::new(&["yes"], &["no"], &[]));
+    let converted = from_json(&value, options).unwrap();
+    assert_eq!(converted.value.to_source(Indent::spaces(2)), "a true false null\n");
+    assert_matches!(&converted.losses[..], [loss] => assert_eq!(loss.path, "/0/statement/3"));
+
+    let value = json!([{ "statement": ["a", "true"] }]);
+    let converted = from_json(&value, options).unwrap();
+    assert_matches!(&converted.losses[..], [] => ());
+    let options = ParseOptions::new(Indent::spaces(2)).literals(Literals::DEFAULT);
+    let converted = from_json(&value, options).unwrap();
+    assert_matches!(&converted.losses[..], [loss] => assert_eq!(loss.path, "/0/statement/1"));
+}
+
+#[test]
+fn sigils() {
+    let value = json!([{ "statement": ["a", { "sigil": "$", "name": "b" }] }]);
+    let options = ParseOptions::new(Indent::spaces(2)).sigils(&['$']);
+    let converted = from_json(&value, options).unwrap();
+    assert!(converted.is_lossless());
+    assert_eq!(converted.value.to_source(Indent::spaces(2)), "a $b\n");
+    assert_eq!(convert::to_json(&converted.value).value, value);
+
+    let converted = from_json(&value, Indent::spaces(2).into()).unwrap();
+    assert_matches!(&converted.losses[..], [loss] => {
+        assert_eq!(loss.path, "/0/statement/1");
+        assert_matches!(&loss.kind, LossKind::Reinterpreted { word } => assert_eq!(word, "$b"));
+    });
+
+    let converted = from_json(&json!([{ "statement": ["a", "$b"] }]), options).unwrap();
+    assert_matches!(&converted.losses[..], [loss] => assert_eq!(loss.path, "/0/statement/1"));
+
+    let value = json!([{ "statement": [{ "sigil": "$$", "name": "b" }] }]);
+    let error = from_json(&value, options).unwrap_err();
+    assert_eq!(error.kind, ConvertErrorKind::InvalidSigil);
+}
+
+#[test]
+fn comments() {
+    let value = json!([
+        { "directive": ["a"], "arguments": [], "children": [
+            { "statement": ["b"], "docs": ["first", ""], "comment": "trailing" },
+        ], "comment": "" },
+    ]);
+    let options = ParseOptions::new(Indent::spaces(2)).trailing_comments(true);
+    let converted = from_json(&value, options).unwrap();
+    assert!(converted.is_lossless());
+    assert_eq!(
+        converted.value.to_source(Indent::spaces(2)),
+        "a: ;\n  ;; first\n  ;;\n  b ; trailing\n",
+    );
+    assert_eq!(convert::to_json(&converted.value).value, value);
+
+    let value = json!([{ "statement": ["a"], "docs": ["x\ny"] }]);
+    let error = from_json(&value, options).unwrap_err();
+    assert_eq!(error.path, "/0/docs/0");
+    assert_eq!(error.kind, ConvertErrorKind::InvalidComment);
+}
+
+#[test]
+fn pairs() {
+    let value = json!([
+        { "statement": ["a", { "pair": ["b", { "brackets": [1] }], "separator": "=" }] },
+    ]);
+    let options = ParseOptions::new(Indent::spaces(2)).pair_separator('=');
+    let converted = from_json(&value, options).unwrap();
+    assert!(converted.is_lossless());
+    assert_eq!(converted.value.to_source(Indent::spaces(2)), "a b=[1]\n");
+    assert_eq!(convert::to_json(&converted.value).value, value);
+
+    let value = json!([{ "statement": [{ "pair": ["b", 1] }] }]);
+    let error = from_json(&value, options).unwrap_err();
+    assert_eq!(error.path, "/0/statement/0");
+    assert_eq!(error.kind, ConvertErrorKind::InvalidPair);
+
+    let value = json!([{ "statement": ["a", { "pair": ["b", 1], "separator": "/" }] }]);
+    let converted = from_json(&value, options).unwrap();
+    assert_eq!(converted.value.to_source(Indent::spaces(2)), "a b/1\n");
+    assert_matches!(&converted.losses[..], [loss] => {
+        assert_eq!(loss.path, "/0/statement/1");
+        assert_matches!(&loss.kind, LossKind::Reinterpreted { word } => assert_eq!(word, "b/"));
+    });
+
+    let converted = from_json(&json!([{ "statement": ["a", "b=c"] }]), options).unwrap();
+    assert_matches!(&converted.losses[..], [loss] => assert_eq!(loss.path, "/0/statement/1"));
+
+    let value = json!([{ "statement": [{ "pair": ["b", "c=d"], "separator": "=" }] }]);
+    let error = from_json(&value, options).unwrap_err();
+    assert_eq!(error.path, "/0/statement/0/pair/1");
+    assert_matches!(error.kind, ConvertErrorKind::InvalidWord { .. });
+}
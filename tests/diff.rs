@@ -0,0 +1,90 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, ParseOptions, Literals};
+use treelang::diff::{Diff, Edit, Path, Step};
+use assert_matches::assert_matches;
+
+
+fn parse(map: &mut SourceMap, name: &str, content: &str) -> Tree {
+    let index = map.insert(Origin::from_named(name), content.into())
+        .try_into_inserted().unwrap();
+    Tree::parse(map.input(index), Indent::spaces(2)).unwrap()
+}
+
+fn diff(old: &str, new: &str) -> Diff {
+    let mut map = SourceMap::new();
+    let old = parse(&mut map, "old", old);
+    let new = parse(&mut map, "new", new);
+    Diff::new(&old, &new)
+}
+
+#[test]
+fn trees() {
+    let diff = diff(&normalize("
+        |server:
+        |  port 80
+        |  host a
+        |  tls:
+        |    cert x
+        |log: info (debug)
+        |extra
+    "), &normalize("
+        |log: info (trace) 2
+        |server:
+        |  host a
+        |  port 81
+        |  tls:
+        |    cert x
+        |metrics
+    "));
+    assert_eq!(diff.to_string(), concat!(
+        "> /0 -> /1\n",
+        "- /0/0 `port 80`\n",
+        "+ /1/1 `port 81`\n",
+        "~ /0/arguments/1/0 `debug` -> `trace`\n",
+        "+ /0/arguments/2 `2`\n",
+        "- /2 `extra`\n",
+        "+ /2 `metrics`\n",
+    ));
+    assert_matches!(&diff.edits[3], Edit::ModifyItem { from, to, .. } => {
+        assert_eq!(from, &Path(vec![Step::Node(1), Step::Argument(1), Step::Group(0)]));
+        assert_eq!(to, &Path(vec![Step::Node(0), Step::Argument(1), Step::Group(0)]));
+    });
+}
+
+#[test]
+fn items() {
+    assert!(diff("a: 1\n  b [c]", "a:   1 ;comment\n\n  b [c]").is_empty());
+    assert_eq!(diff("port 80", "port 81").to_string(), "~ /0/signature/1 `80` -> `81`\n");
+    assert_eq!(diff("a b c", "a c b").to_string(), "> /0/signature/1 -> /0/signature/2\n");
+    assert_eq!(diff("a: 1.0", "a: 1").to_string(), "~ /0/arguments/0 `1.0` -> `1`\n");
+    assert_eq!(diff("a", "a:").to_string(), "- /0 `a`\n+ /0 `a:`\n");
+    assert_eq!(diff("a:\n  b", "a: x").to_string(), "+ /0/arguments/0 `x`\n- /0/0 `b`\n");
+}
+
+#[test]
+fn long_sequences() {
+    let old = (0..2000).map(|index| format!("a{}\n", index)).collect::<String>();
+    let new = old.replace("a10\n", "").replace("a1500\n", "b\na1500\n");
+    assert_eq!(diff(&old, &new).to_string(), "- /10 `a10`\n+ /1499 `b`\n");
+
+    let new = (0..2000).rev().map(|index| format!("a{}\n", index)).collect::<String>();
+    assert_eq!(diff(&old, &new).edits.len(), 1999);
+}
+
+#[test]
+fn item_kinds() {
+    let mut map = SourceMap::new();
+    let mut parse_with = |name, options| {
+        let index = map.insert(Origin::from_named(name), "a true $b\n".into())
+            .try_into_inserted().unwrap();
+        Tree::parse_with(map.input(index), options).unwrap()
+    };
+    let words = parse_with("words", Indent::spaces(2).into());
+    let options = ParseOptions::new(Indent::spaces(2)).literals(Literals::DEFAULT).sigils(&['$']);
+    let kinds = parse_with("kinds", options);
+    assert_eq!(Diff::new(&words, &kinds).to_string(), concat!(
+        "~ /0/signature/1 `true` -> `true`\n",
+        "~ /0/signature/2 `$b` -> `$b`\n",
+    ));
+    assert!(Diff::new(&kinds, &kinds).is_empty());
+}
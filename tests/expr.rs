@@ -0,0 +1,163 @@
+use src_ctx::{SourceMap, Origin};
+use treelang::{Indent, Tree, Item, ParseOptions, Literals};
+use treelang::expr::{
+    OperatorTable, Associativity, Expr, ExprKind, ExprError, Evaluator, Value, EvalError,
+};
+use assert_matches::assert_matches;
+
+
+fn parse(content: &str) -> (Tree, SourceMap) {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    (tree, map)
+}
+
+fn group(tree: &Tree) -> Item {
+    tree[0].statement().unwrap().signature[0].clone()
+}
+
+fn operators() -> OperatorTable {
+    OperatorTable::new()
+        .infix("and", 0, Associativity::Left)
+        .infix("<", 1, Associativity::None)
+        .infix("+", 2, Associativity::Left)
+        .infix("*", 3, Associativity::Left)
+        .infix("^", 4, Associativity::Right)
+        .prefix("!", 5)
+        .postfix("?", 6)
+}
+
+fn show(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Operand(item) => item.to_string(),
+        ExprKind::Prefix { operator, operand } => {
+            format!("({}{})", operator.symbol, show(operand))
+        },
+        ExprKind::Infix { operator, left, right } => {
+            format!("({} {} {})", show(left), operator.symbol, show(right))
+        },
+        ExprKind::Postfix { operator, operand } => {
+            format!("({}{})", show(operand), operator.symbol)
+        },
+    }
+}
+
+fn parse_expr(content: &str) -> String {
+    let (tree, _) = parse(content);
+    show(&operators().parse_group(&group(&tree)).unwrap())
+}
+
+fn parse_error(content: &str) -> (ExprError, usize) {
+    let (tree, map) = parse(content);
+    let error = operators().parse_group(&group(&tree)).unwrap_err();
+    (error.error().clone(), map.byte_offset_on_line(error.offset()))
+}
+
+#[test]
+fn precedence() {
+    assert_eq!(parse_expr("(a + b * 2)"), "(a + (b * 2))");
+    assert_eq!(parse_expr("(a * b + 2)"), "((a * b) + 2)");
+    assert_eq!(parse_expr("(a + b + c)"), "((a + b) + c)");
+    assert_eq!(parse_expr("(a ^ b ^ c)"), "(a ^ (b ^ c))");
+    assert_eq!(parse_expr("((a + b) * c)"), "((a + b) * c)");
+    assert_eq!(parse_expr("(! a ? * b)"), "((!(a?)) * b)");
+    assert_eq!(parse_expr("(a < b and [c] < 1.5)"), "((a < b) and ([c] < 1.5))");
+    assert_eq!(parse_expr("[x]"), "x");
+}
+
+#[test]
+fn locations() {
+    let (tree, map) = parse("(a + b * 2)");
+    let expr = operators().parse_group(&group(&tree)).unwrap();
+    assert_eq!(map.span_str(expr.location), "a + b * 2");
+    assert_matches!(expr.kind, ExprKind::Infix { operator, left, right } => {
+        assert_eq!(map.span_str(operator.location), "+");
+        assert_eq!(map.span_str(left.location), "a");
+        assert_eq!(map.span_str(right.location), "b * 2");
+    });
+}
+
+#[test]
+fn errors() {
+    assert_eq!(parse_error("()"), (ExprError::EmptyExpression, 0));
+    assert_eq!(parse_error("(a + ())"), (ExprError::EmptyExpression, 5));
+    assert_eq!(parse_error("(a +)"), (ExprError::MissingOperand { operator: "+".into() }, 3));
+    assert_eq!(parse_error("(* a)"), (ExprError::MissingOperand { operator: "*".into() }, 1));
+    assert_eq!(parse_error("(a + * b)"), (ExprError::MissingOperand { operator: "*".into() }, 5));
+    assert_eq!(parse_error("(a b)"), (ExprError::MissingOperator, 3));
+    assert_eq!(parse_error("(a % b)"), (ExprError::UnknownOperator { operator: "%".into() }, 3));
+    assert_eq!(parse_error("(a < b < c)"), (ExprError::NonAssociative { operator: "<".into() }, 7));
+    assert_eq!(parse_error("(a ! b)"), (ExprError::MissingOperand { operator: "!".into() }, 3));
+    assert_eq!(parse_error("a"), (ExprError::ExpectedGroup, 0));
+}
+
+fn evaluate(content: &str) -> Result<Value, (EvalError, usize)> {
+    let (tree, map) = parse(&format!("value: {}", content));
+    let directive = tree[0].directive().unwrap();
+    Evaluator::new().evaluate_arguments(directive)
+        .map(|values| values[0])
+        .map_err(|error| (error.error().clone(), map.byte_offset_on_line(error.offset())))
+}
+
+#[test]
+fn evaluation() {
+    assert_eq!(evaluate("23"), Ok(Value::Int(23)));
+    assert_eq!(evaluate("(1 + 2 * 3)"), Ok(Value::Int(7)));
+    assert_eq!(evaluate("((1 + 2) * 3)"), Ok(Value::Int(9)));
+    assert_eq!(evaluate("(7 / 2 + 7 % 2)"), Ok(Value::Int(4)));
+    assert_eq!(evaluate("(1 + 0.5)"), Ok(Value::Float(1.5)));
+    assert_eq!(evaluate("(2 * -1.5)"), Ok(Value::Float(-3.0)));
+    assert_eq!(evaluate("(1 < 2 && ! (2.0 == 2))"), Ok(Value::Bool(false)));
+    assert_eq!(evaluate("(1 >= 2 || 3 != 4)"), Ok(Value::Bool(true)));
+    assert_eq!(evaluate("(10 sub 2 sub 3 + 1)"), Ok(Value::Int(6)));
+    assert_eq!(evaluate("(neg 2 * 3)"), Ok(Value::Int(-6)));
+    assert_eq!(evaluate("(1 sub neg 0.5)"), Ok(Value::Float(1.5)));
+
+    let (tree, _) = parse("value: 1 (2 + 2) 0.5");
+    let values = Evaluator::new().evaluate_arguments(tree[0].directive().unwrap()).unwrap();
+    assert_eq!(values, [Value::Int(1), Value::Int(4), Value::Float(0.5)]);
+    assert_eq!(Value::Float(4.0).to_string(), "4.0");
+}
+
+#[test]
+fn boolean_literals() {
+    let mut map = SourceMap::new();
+    let content = "value: true (! false && 1 < 2)";
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2)).literals(Literals::DEFAULT);
+    let tree = Tree::parse_with(map.input(index), options).unwrap();
+    let values = Evaluator::new().evaluate_arguments(tree[0].directive().unwrap()).unwrap();
+    assert_eq!(values, [Value::Bool(true), Value::Bool(true)]);
+}
+
+#[test]
+fn evaluation_errors() {
+    assert_eq!(evaluate("(1 / 0)"), Err((EvalError::DivisionByZero, 10)));
+    assert_eq!(evaluate("(1.5 % 0)"), Err((EvalError::DivisionByZero, 12)));
+    assert_eq!(
+        evaluate("(2147483647 + 1)"),
+        Err((EvalError::Overflow { operator: "+".into() }, 19)),
+    );
+    assert_eq!(
+        evaluate("(3.0e38 * 10)"),
+        Err((EvalError::Overflow { operator: "*".into() }, 15)),
+    );
+    assert_eq!(
+        evaluate("(1 + (1 < 2))"),
+        Err((EvalError::TypeMismatch { operator: "+".into() }, 10)),
+    );
+    assert_eq!(evaluate("(! 1)"), Err((EvalError::TypeMismatch { operator: "!".into() }, 8)));
+    assert_eq!(
+        evaluate("(neg -2147483648)"),
+        Err((EvalError::Overflow { operator: "neg".into() }, 8)),
+    );
+    assert_eq!(evaluate("(1 + a)"), Err((EvalError::NotConstant, 12)));
+    assert_eq!(evaluate("[1]"), Err((EvalError::NotConstant, 7)));
+    assert_eq!(
+        evaluate("(1 +)"),
+        Err((EvalError::Expr(ExprError::MissingOperand { operator: "+".into() }), 10)),
+    );
+}
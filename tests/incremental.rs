@@ -0,0 +1,188 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, TextEdit, ParseError, ParseOptions, Literals, ItemKind};
+use assert_matches::assert_matches;
+
+
+fn assert_reparse(old: &str, edit: TextEdit) -> Result<Tree, ParseError> {
+    assert_reparse_with(old, edit, ParseOptions::new(Indent::spaces(2)))
+}
+
+fn assert_reparse_with(
+    old: &str,
+    edit: TextEdit,
+    options: ParseOptions,
+) -> Result<Tree, ParseError> {
+    let old = normalize(old);
+    let new = edit.apply(&old);
+
+    let mut map = SourceMap::new();
+    let old_index = map.insert(Origin::from_named("old"), old.into())
+        .try_into_inserted().unwrap();
+    let new_index = map.insert(Origin::from_named("new"), new.into())
+        .try_into_inserted().unwrap();
+
+    let tree = Tree::parse_with(map.input(old_index), options).unwrap();
+    let reparsed = tree.reparse(map.input(old_index), &edit, map.input(new_index), options)
+        .map_err(|error| error.into_error());
+    let parsed = Tree::parse_with(map.input(new_index), options)
+        .map_err(|error| error.into_error());
+
+    assert_eq!(reparsed, parsed);
+    reparsed
+}
+
+const SOURCE: &str = "
+    |first:
+    |  a 1
+    |  b: 2
+    |    c (3 4)
+    |
+    |; comment
+    |second x
+    |third:
+    |  d [5]
+";
+
+#[test]
+fn unchanged() {
+    let tree = assert_reparse(SOURCE, TextEdit::new(0..0, "")).unwrap();
+    assert_eq!(tree.len(), 3);
+}
+
+#[test]
+fn edit_inside_root() {
+    let offset = normalize(SOURCE).find("(3").unwrap();
+    let tree = assert_reparse(SOURCE, TextEdit::new(offset..(offset + 2), "(23")).unwrap();
+    assert_eq!(tree.len(), 3);
+
+    let offset = normalize(SOURCE).find("second").unwrap();
+    let tree = assert_reparse(SOURCE, TextEdit::new(offset..(offset + 6), "other")).unwrap();
+    assert_matches!(&tree[..], [_, node, _] => {
+        assert_eq!(node.statement().unwrap().signature[0].word_str(), Some("other"));
+    });
+}
+
+#[test]
+fn insert_and_remove_roots() {
+    let offset = normalize(SOURCE).find("second").unwrap();
+    let tree = assert_reparse(SOURCE, TextEdit::new(offset..offset, "inserted:\n  y\n")).unwrap();
+    assert_eq!(tree.len(), 4);
+
+    let start = normalize(SOURCE).find("second").unwrap();
+    let end = normalize(SOURCE).find("third").unwrap();
+    let tree = assert_reparse(SOURCE, TextEdit::new(start..end, "")).unwrap();
+    assert_eq!(tree.len(), 2);
+}
+
+#[test]
+fn change_root_depth() {
+    let offset = normalize(SOURCE).find("third").unwrap();
+    let error = assert_reparse(SOURCE, TextEdit::new(offset..offset, "  ")).unwrap_err();
+    assert_eq!(error, ParseError::StatementWithChild);
+
+    let offset = normalize(SOURCE).find("second").unwrap();
+    let tree = assert_reparse(SOURCE, TextEdit::new(offset..offset, "  ")).unwrap();
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree[0].children().len(), 3);
+
+    let offset = normalize(SOURCE).find("  d [5]").unwrap();
+    let tree = assert_reparse(SOURCE, TextEdit::new(offset..(offset + 2), "")).unwrap();
+    assert_eq!(tree.len(), 4);
+}
+
+#[test]
+fn errors_and_skipped_lines() {
+    let offset = normalize(SOURCE).find("[5]").unwrap();
+    let error = assert_reparse(SOURCE, TextEdit::new(offset..(offset + 3), "[5")).unwrap_err();
+    assert_eq!(error, ParseError::UnclosedGroup { missing: ']' });
+
+    let offset = normalize(SOURCE).find("third").unwrap();
+    let tree = assert_reparse(SOURCE, TextEdit::new(offset..offset, "__END__\n")).unwrap();
+    assert_eq!(tree.len(), 2);
+
+    let offset = normalize(SOURCE).find("third").unwrap();
+    let tree = assert_reparse(SOURCE, TextEdit::new(offset..offset, "(;-) ")).unwrap();
+    assert_eq!(tree.len(), 2);
+
+    let offset = normalize(SOURCE).find("third").unwrap();
+    let error = assert_reparse(SOURCE, TextEdit::new(offset..offset, "(;\n")).unwrap_err();
+    assert_eq!(error, ParseError::UnclosedBlockComment);
+}
+
+const DOCUMENTED: &str = "
+    |;; First.
+    |first: 1
+    |  ;; Child.
+    |  child
+    |
+    |;; Second.
+    |; comment
+    |second 2
+    |third
+";
+
+#[test]
+fn doc_comments() {
+    let offset = normalize(DOCUMENTED).find("Second.").unwrap();
+    let tree = assert_reparse(DOCUMENTED, TextEdit::new(offset..(offset + 6), "Other")).unwrap();
+    assert_eq!(tree[1].docs[0].text, "Other.");
+
+    let offset = normalize(DOCUMENTED).find("; comment").unwrap();
+    let tree = assert_reparse(DOCUMENTED, TextEdit::new(offset..offset, ";")).unwrap();
+    assert_eq!(tree[1].docs.len(), 2);
+
+    let offset = normalize(DOCUMENTED).find("second").unwrap();
+    let tree = assert_reparse(DOCUMENTED, TextEdit::new(offset..offset, "inserted\n")).unwrap();
+    assert_eq!(tree[1].docs.len(), 1);
+    assert!(tree[2].docs.is_empty());
+
+    let offset = normalize(DOCUMENTED).find("2").unwrap();
+    let tree = assert_reparse(DOCUMENTED, TextEdit::new(offset..(offset + 1), "3")).unwrap();
+    assert_eq!(tree[1].docs.len(), 1);
+
+    let offset = normalize(DOCUMENTED).find("third").unwrap();
+    let tree = assert_reparse(DOCUMENTED, TextEdit::new(offset..offset, ";; Third.\n")).unwrap();
+    assert_eq!(tree[2].docs[0].text, "Third.");
+}
+
+const OPTIONS_SOURCE: &str = "
+    |first: a=1 $x ; one
+    |  b true (c=[2])
+    |second null
+    |third: @y ; three
+    |  d e=f
+";
+
+#[test]
+fn parse_options() {
+    let options = ParseOptions::new(Indent::spaces(2))
+        .pair_separator('=')
+        .literals(Literals::DEFAULT)
+        .sigils(&['$', '@'])
+        .trailing_comments(true);
+
+    let offset = normalize(OPTIONS_SOURCE).find("null").unwrap();
+    let edit = TextEdit::new(offset..(offset + 4), "g=false ; two");
+    let tree = assert_reparse_with(OPTIONS_SOURCE, edit, options).unwrap();
+    assert!(tree[0].directive().unwrap().arguments[0].is_pair());
+    assert_eq!(tree[0].comment.as_ref().unwrap().text, "one");
+    assert_eq!(tree[1].comment.as_ref().unwrap().text, "two");
+    assert_eq!(tree[2].directive().unwrap().arguments[0].sigil_name('@'), Some("y"));
+    assert!(tree[2].children()[0].statement().unwrap().signature[1].is_pair());
+
+    let offset = normalize(OPTIONS_SOURCE).find("true").unwrap();
+    let edit = TextEdit::new(offset..(offset + 4), "false");
+    let tree = assert_reparse_with(OPTIONS_SOURCE, edit, options).unwrap();
+    assert_eq!(tree[0].children()[0].statement().unwrap().signature[1].kind, ItemKind::Bool(false));
+    assert!(tree[1].statement().unwrap().signature[1].is_null());
+
+    let offset = normalize(OPTIONS_SOURCE).find("b true").unwrap();
+    let edit = TextEdit::new(offset..offset, "h ");
+    let error = assert_reparse_with(OPTIONS_SOURCE, edit, options.max_items(6)).unwrap_err();
+    assert_eq!(error, ParseError::ItemLimit { limit: 6 });
+
+    let offset = normalize(OPTIONS_SOURCE).find("second").unwrap();
+    let edit = TextEdit::new(offset..offset, "inserted\n");
+    let error = assert_reparse_with(OPTIONS_SOURCE, edit, options.max_nodes(5)).unwrap_err();
+    assert_eq!(error, ParseError::NodeLimit { limit: 5 });
+}
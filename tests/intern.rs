@@ -0,0 +1,86 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, ParseOptions, Literals};
+use treelang::intern::{self, Interner, ItemKind};
+use assert_matches::assert_matches;
+
+
+#[test]
+fn shared_across_parses() {
+    let mut map = SourceMap::new();
+    let first = map.insert(Origin::from_named("first"), "server: main\n  port 80".into())
+        .try_into_inserted().unwrap();
+    let second = map.insert(Origin::from_named("second"), "server: backup 8080".into())
+        .try_into_inserted().unwrap();
+
+    let mut interner = Interner::new();
+    let first = intern::Tree::parse(map.input(first), Indent::spaces(2), &mut interner).unwrap();
+    let second = intern::Tree::parse(map.input(second), Indent::spaces(2), &mut interner)
+        .unwrap();
+    assert_eq!(interner.len(), 4);
+
+    let server = interner.get("server").unwrap();
+    assert_eq!(first[0].directive().unwrap().signature[0].word(), Some(server));
+    assert_eq!(second[0].directive().unwrap().signature[0].word(), Some(server));
+    assert_eq!(interner.resolve(server), "server");
+    assert_eq!(server.index(), 0);
+
+    let arguments = &second[0].directive().unwrap().arguments;
+    assert_matches!(arguments[1].kind, ItemKind::Int(8080));
+    assert_eq!(interner.get("missing"), None);
+}
+
+#[test]
+fn intern() {
+    let mut interner = Interner::new();
+    assert!(interner.is_empty());
+    let a = interner.intern("a");
+    let b = interner.intern("b");
+    assert_ne!(a, b);
+    assert_eq!(interner.intern("a"), a);
+    assert_eq!(interner.len(), 2);
+    assert_eq!(interner.resolve(b), "b");
+}
+
+#[test]
+fn resolve() {
+    let content = normalize("
+        |abc: def [1 {2 x}] -3
+        |  ghi (def 2.5)
+        |jkl
+    ");
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let mut interner = Interner::new();
+    let tree = intern::Tree::parse(map.input(index), Indent::spaces(2), &mut interner).unwrap();
+    let owned = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    assert_eq!(tree.resolve(&interner), owned);
+    assert_eq!(interner.len(), 5);
+}
+
+#[test]
+fn parse_options() {
+    let mut map = SourceMap::new();
+    let content = "server: port=80 $host true ; primary\n  tls null";
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2))
+        .pair_separator('=')
+        .sigils(&['$'])
+        .literals(Literals::DEFAULT)
+        .trailing_comments(true);
+    let mut interner = Interner::new();
+    let tree = intern::Tree::parse_with(map.input(index), options, &mut interner).unwrap();
+
+    let directive = tree[0].directive().unwrap();
+    let (key, value) = directive.arguments[0].pair().unwrap();
+    assert_eq!(key.word(), interner.get("port"));
+    assert_eq!(value.int(), Some(80));
+    assert_matches!(directive.arguments[1].kind, ItemKind::Sigil { sigil: '$', .. });
+    assert_eq!(directive.arguments[2].bool(), Some(true));
+    assert_eq!(tree[0].comment.as_ref().map(|comment| comment.text.as_str()), Some("primary"));
+    assert!(directive.children[0].statement().unwrap().signature[1].is_null());
+
+    let owned = Tree::parse_with(map.input(index), options).unwrap();
+    assert_eq!(tree.resolve(&interner), owned);
+}
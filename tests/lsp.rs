@@ -0,0 +1,256 @@
+#![cfg(feature = "lsp")]
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value};
+
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Client {
+    fn start() -> Self {
+        Self::start_with(json!({ "indent": 2 }))
+    }
+
+    fn start_with(options: Value) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_treelang-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Self { child, stdin, stdout, next_id: 0 };
+        client.request("initialize", json!({
+            "capabilities": {},
+            "initializationOptions": options,
+        }));
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = Some(value.parse().unwrap());
+            }
+        }
+        let mut body = vec![0; length.unwrap()];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let mut message = self.receive();
+            if message["id"] == id {
+                return message["result"].take();
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn notification(&mut self, method: &str) -> Value {
+        loop {
+            let mut message = self.receive();
+            if message["method"] == method {
+                return message["params"].take();
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) -> Value {
+        self.notify("textDocument/didOpen", json!({
+            "textDocument": { "uri": uri, "languageId": "treelang", "version": 1, "text": text },
+        }));
+        self.notification("textDocument/publishDiagnostics")
+    }
+
+    fn shutdown(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+const URI: &str = "file:///test.tl";
+
+#[test]
+fn diagnostics() {
+    let mut client = Client::start();
+
+    client.notify("textDocument/didOpen", json!({ "textDocument": { "uri": 23 } }));
+    let published = client.open(URI, "abc:\n  def (ghi\n");
+    assert_eq!(published["diagnostics"].as_array().unwrap().len(), 1);
+    let diagnostic = &published["diagnostics"][0];
+    assert_eq!(diagnostic["range"], json!({
+        "start": { "line": 1, "character": 6 },
+        "end": { "line": 1, "character": 10 },
+    }));
+    assert_eq!(diagnostic["severity"], 1);
+
+    client.notify("textDocument/didChange", json!({
+        "textDocument": { "uri": URI, "version": 2 },
+        "contentChanges": [{ "text": "abc\n  def\n" }],
+    }));
+    let published = client.notification("textDocument/publishDiagnostics");
+    let diagnostic = &published["diagnostics"][0];
+    assert_eq!(diagnostic["range"]["end"], json!({ "line": 1, "character": 5 }));
+    assert_eq!(diagnostic["relatedInformation"], json!([{
+        "location": {
+            "uri": URI,
+            "range": {
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 0, "character": 3 },
+            },
+        },
+        "message": "related location",
+    }]));
+
+    client.notify("textDocument/didChange", json!({
+        "textDocument": { "uri": URI, "version": 2 },
+        "contentChanges": [{ "text": "abc:\n  def (ghi)\n" }],
+    }));
+    let published = client.notification("textDocument/publishDiagnostics");
+    assert_eq!(published["diagnostics"], json!([]));
+
+    client.shutdown();
+}
+
+#[test]
+fn symbols_and_folding() {
+    let mut client = Client::start();
+    client.open(URI, "abc def: x\n  ghi:\n    jkl\n  mno\n");
+
+    let symbols = client.request("textDocument/documentSymbol", json!({
+        "textDocument": { "uri": URI },
+    }));
+    assert_eq!(symbols[0]["name"], "abc def");
+    assert_eq!(symbols[0]["detail"], "x");
+    assert_eq!(symbols[0]["range"]["end"], json!({ "line": 3, "character": 5 }));
+    assert_eq!(symbols[0]["children"][0]["name"], "ghi");
+
+    let ranges = client.request("textDocument/foldingRange", json!({
+        "textDocument": { "uri": URI },
+    }));
+    assert_eq!(ranges, json!([
+        { "startLine": 0, "endLine": 3 },
+        { "startLine": 1, "endLine": 2 },
+    ]));
+
+    client.shutdown();
+}
+
+#[test]
+fn formatting_and_hover() {
+    let mut client = Client::start();
+    client.open(URI, "abc  def:  23\n  (ghi   1.5) ; comment\n");
+
+    let edits = client.request("textDocument/formatting", json!({
+        "textDocument": { "uri": URI },
+        "options": { "tabSize": 2, "insertSpaces": true },
+    }));
+    assert_eq!(edits, Value::Null);
+
+    for (uri, text, expected) in [
+        ("file:///plain.tl", "abc  def:  23\n  (ghi   1.5)\n", json!("abc def: 23\n  (ghi 1.5)\n")),
+        ("file:///end.tl", "abc  def\n__END__\nnot ( parsed\n", Value::Null),
+        ("file:///doc.tl", ";; Doc.\nabc  def\n", json!(";; Doc.\nabc def\n")),
+    ] {
+        client.open(uri, text);
+        let edits = client.request("textDocument/formatting", json!({
+            "textDocument": { "uri": uri },
+            "options": { "tabSize": 2, "insertSpaces": true },
+        }));
+        assert_eq!(edits.get(0).map_or(&edits, |edit| &edit["newText"]), &expected);
+    }
+
+    let hover = client.request("textDocument/hover", json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": 1, "character": 9 },
+    }));
+    assert_eq!(hover["contents"]["value"], "float `1.5`");
+
+    let hover = client.request("textDocument/hover", json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": 0, "character": 11 },
+    }));
+    assert_eq!(hover["contents"]["value"], "int `23`");
+
+    client.shutdown();
+}
+
+#[test]
+fn hover_docs() {
+    let mut client = Client::start();
+    client.open(URI, ";; The server.\n;; Second line.\nserver: main\n  port 80\n");
+
+    let hover = client.request("textDocument/hover", json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": 2, "character": 1 },
+    }));
+    assert_eq!(hover["contents"]["value"], "word `server`\n\nThe server.\nSecond line.");
+
+    let hover = client.request("textDocument/hover", json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": 3, "character": 3 },
+    }));
+    assert_eq!(hover["contents"]["value"], "word `port`");
+
+    client.shutdown();
+}
+
+#[test]
+fn parse_options() {
+    let mut client = Client::start_with(json!({
+        "indent": 2,
+        "literals": { "true": ["yes"] },
+        "sigils": "$",
+        "pairSeparator": "=",
+    }));
+    client.open(URI, "a yes $x b=1\n");
+
+    for (character, expected) in [(2, "bool `true`"), (6, "sigil `$x`"), (10, "pair `b=1`")] {
+        let hover = client.request("textDocument/hover", json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": 0, "character": character },
+        }));
+        assert_eq!(hover["contents"]["value"], expected);
+    }
+
+    client.shutdown();
+
+    let mut client = Client::start_with(json!({ "sigils": ":", "pairSeparator": "==" }));
+    client.open(URI, "a:\n  b==c\n");
+    let hover = client.request("textDocument/hover", json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": 1, "character": 3 },
+    }));
+    assert_eq!(hover["contents"]["value"], "word `b==c`");
+
+    client.shutdown();
+}
@@ -0,0 +1,114 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree};
+use treelang::merge::{Merge, Strategy, NodeSource, Provenance};
+
+
+fn parse(map: &mut SourceMap, name: &str, content: &str) -> Tree {
+    let index = map.insert(Origin::from_named(name), content.into())
+        .try_into_inserted().unwrap();
+    Tree::parse(map.input(index), Indent::spaces(2)).unwrap()
+}
+
+fn base(map: &mut SourceMap) -> Tree {
+    parse(map, "base", &normalize("
+        |server:
+        |  port 80
+        |  tls:
+        |    cert a.pem
+        |  routes:
+        |    route /a
+        |log: info
+        |debug
+    "))
+}
+
+#[test]
+fn deep_merge() {
+    let mut map = SourceMap::new();
+    let base = base(&mut map);
+    let overlay = parse(&mut map, "overlay", &normalize("
+        |server:
+        |  tls:
+        |    key b.pem
+        |  routes:
+        |    route /b
+        |log: warn
+        |delete debug
+        |metrics: on
+    "));
+    let merged = Merge::default()
+        .delete_marker("delete")
+        .strategy(&["server", "routes"], Strategy::Replace)
+        .apply(&base, &overlay);
+    assert_eq!(merged.tree.to_source(Indent::spaces(2)), concat!(
+        "server:\n",
+        "  port 80\n",
+        "  tls:\n",
+        "    cert a.pem\n",
+        "    key b.pem\n",
+        "  routes:\n",
+        "    route /b\n",
+        "log: warn\n",
+        "metrics: on\n",
+    ));
+
+    let base_server = &base[0];
+    let overlay_server = &overlay[0];
+    let overlay_tls = &overlay_server.children()[0];
+    let overlay_routes = &overlay_server.children()[1];
+    let provenance = |path: &[usize], source| Provenance { path: path.to_vec(), source };
+    assert_eq!(merged.provenance, [
+        provenance(&[0], NodeSource::Merged {
+            base: base_server.location,
+            overlay: overlay_server.location,
+        }),
+        provenance(&[0, 0], NodeSource::Base(base_server.children()[0].location)),
+        provenance(&[0, 1], NodeSource::Merged {
+            base: base_server.children()[1].location,
+            overlay: overlay_tls.location,
+        }),
+        provenance(&[0, 1, 0], NodeSource::Base(base_server.children()[1].children()[0].location)),
+        provenance(&[0, 1, 1], NodeSource::Overlay(overlay_tls.children()[0].location)),
+        provenance(&[0, 2], NodeSource::Overlay(overlay_routes.location)),
+        provenance(&[0, 2, 0], NodeSource::Overlay(overlay_routes.children()[0].location)),
+        provenance(&[1], NodeSource::Merged {
+            base: base[1].location,
+            overlay: overlay[1].location,
+        }),
+        provenance(&[2], NodeSource::Overlay(overlay[3].location)),
+    ]);
+}
+
+#[test]
+fn strategies() {
+    let mut map = SourceMap::new();
+    let base = base(&mut map);
+    let overlay = parse(&mut map, "overlay", "server:\n  port 80\n  port 81\ndebug");
+
+    let merged = Merge::new(Strategy::AppendChildren).apply(&base, &overlay);
+    assert_eq!(merged.tree.to_source(Indent::tabs()), concat!(
+        "server:\n\tport 80\n\ttls:\n\t\tcert a.pem\n\troutes:\n\t\troute /a\n",
+        "\tport 80\n\tport 81\n",
+        "log: info\n",
+        "debug\n",
+    ));
+
+    let merged = Merge::new(Strategy::Replace).apply(&base, &overlay);
+    assert_eq!(
+        merged.tree.to_source(Indent::tabs()),
+        "server:\n\tport 80\n\tport 81\nlog: info\ndebug\n",
+    );
+
+    let merged = Merge::default().strategy(&["debug"], Strategy::Delete).apply(&base, &overlay);
+    assert_eq!(merged.tree.len(), 2);
+    assert_eq!(merged.tree[0].children().len(), 4);
+
+    let merged = Merge::default().apply(&base, &Tree::default());
+    assert_eq!(merged.tree, base);
+    assert!(merged.provenance.iter().all(|entry| matches!(entry.source, NodeSource::Base(_))));
+
+    let overlay = parse(&mut map, "repeated", "extra:\n  a\nextra:\n  b\n");
+    let merged = Merge::default().apply(&Tree::default(), &overlay);
+    assert_eq!(merged.tree.to_source(Indent::tabs()), "extra:\n\ta\n\tb\n");
+    assert_eq!(merged.provenance[0].source, NodeSource::Overlay(overlay[0].location));
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "rayon")]
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, ParseError, ParseOptions, Literals, parse_all};
+use assert_matches::assert_matches;
+
+
+#[test]
+fn parse_all_in_order() {
+    let sources = ["a: 1\n  b", "c [2", "d\n  e", "f {g}"];
+    let mut map = SourceMap::new();
+    let indices: Vec<_> = sources.iter().enumerate()
+        .map(|(index, content)| {
+            let origin = Origin::from_named(format!("source-{}", index).as_str());
+            map.insert(origin, (*content).into()).try_into_inserted().unwrap()
+        })
+        .collect();
+
+    let inputs = indices.iter().enumerate().map(|(key, &index)| (key, map.input(index)));
+    let results = parse_all(inputs, ParseOptions::new(Indent::spaces(2)));
+    let keys: Vec<_> = results.iter().map(|(key, _)| *key).collect();
+    assert_eq!(keys, [0, 1, 2, 3]);
+
+    for ((_, result), &index) in results.iter().zip(&indices) {
+        let expected = Tree::parse(map.input(index), Indent::spaces(2));
+        assert_eq!(
+            result.as_ref().map_err(|error| error.error()),
+            expected.as_ref().map_err(|error| error.error()),
+        );
+    }
+    assert_matches!(&results[1].1, Err(error) if error.error() == &ParseError::UnclosedGroup {
+        missing: ']',
+    });
+    assert_matches!(&results[2].1, Err(error) if error.error() == &ParseError::StatementWithChild);
+}
+
+fn large_source(extra: &str) -> String {
+    let mut content = String::new();
+    for index in 0..500 {
+        content.push_str(&format!("root-{}: {}\n  child [{}]\n", index, index, index));
+        content.push_str("  ; comment\n");
+        if index == 300 {
+            content.push_str(extra);
+        }
+    }
+    content
+}
+
+fn assert_split_same(content: &str) {
+    assert_split_same_with(content, ParseOptions::new(Indent::spaces(2))).ok();
+}
+
+fn assert_split_same_with(content: &str, options: ParseOptions) -> Result<Tree, ParseError> {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let split = Tree::parse_split_with(map.input(index), options)
+        .map_err(|error| error.into_error());
+    let parsed = Tree::parse_with(map.input(index), options)
+        .map_err(|error| error.into_error());
+    assert_eq!(split, parsed);
+    split
+}
+
+#[test]
+fn parse_split() {
+    assert_split_same(&large_source(""));
+    assert_split_same(&large_source("stray\n    indent\n"));
+    assert_split_same(&large_source("__END__\n"));
+    assert_split_same(&normalize("
+        |a:
+        |  b
+    "));
+}
+
+#[test]
+fn parse_options() {
+    let options = ParseOptions::new(Indent::spaces(2))
+        .pair_separator('=')
+        .literals(Literals::DEFAULT)
+        .trailing_comments(true);
+    let tree = assert_split_same_with(&large_source("pairs a=1 b=true ; note\n"), options);
+    assert!(tree.unwrap()[301].statement().unwrap().signature[1].is_pair());
+    let error = assert_split_same_with(&large_source("a b c d\n"), options.max_items(3));
+    assert_eq!(error, Err(ParseError::ItemLimit { limit: 3 }));
+    let error = assert_split_same_with(&large_source(""), options.max_nodes(600));
+    assert_eq!(error, Err(ParseError::NodeLimit { limit: 600 }));
+
+    let mut map = SourceMap::new();
+    let first = map.insert(Origin::from_named("first"), "a=1".into())
+        .try_into_inserted().unwrap();
+    let second = map.insert(Origin::from_named("second"), "b c d".into())
+        .try_into_inserted().unwrap();
+    let inputs = [(0, map.input(first)), (1, map.input(second))];
+    let results = parse_all(inputs, options.max_items(2));
+    assert_matches!(&results[0].1, Ok(tree) => {
+        assert!(tree[0].statement().unwrap().signature[0].is_pair());
+    });
+    assert_matches!(&results[1].1, Err(error) if error.error() == &ParseError::ItemLimit {
+        limit: 2,
+    });
+}
@@ -1,5 +1,8 @@
 use src_ctx::{SourceMap, Origin, normalize};
-use treelang::{ParseResult, Indent, Tree, ParseError, Statement, Directive};
+use treelang::{
+    ParseResult, Indent, Tree, ParseError, ParseOptions, Literals, Statement, Directive, ItemKind,
+    Locator,
+};
 use assert_matches::assert_matches;
 
 
@@ -151,6 +154,112 @@ fn comments() {
     });
 }
 
+#[test]
+fn block_comments() {
+    let (tree, _) = parse(&normalize("
+        |abc:
+        |  (; disabled:
+        |      badly
+        |   indented ;)
+        |  def
+        |(; single line ;)
+        |ghi
+    ")).unwrap();
+
+    assert_matches!(&tree[..], [abc, ghi] => {
+        assert_matches!(abc.children(), [def] => {
+            assert_eq!(def.statement().unwrap().signature[0].word_str(), Some("def"));
+        });
+        assert_eq!(ghi.statement().unwrap().signature[0].word_str(), Some("ghi"));
+    });
+
+    assert_parsed!(map = "
+            |abc
+            |(; unclosed
+            |def
+        ",
+        Err(ParseError::UnclosedBlockComment)
+    );
+}
+
+#[test]
+fn disabled_nodes() {
+    let (tree, _) = parse(&normalize("
+        |abc:
+        |  ;; Dropped with the node.
+        |  (;-) def:
+        |       ghi
+        |
+        |    jkl
+        |  mno
+        |(;-) pqr:
+        |; note
+        |    vwx
+        |
+        |;; Kept.
+        |stu
+    ")).unwrap();
+
+    assert_matches!(&tree[..], [abc, stu] => {
+        assert_matches!(abc.children(), [mno] => {
+            assert!(mno.docs.is_empty());
+            assert_eq!(mno.statement().unwrap().signature[0].word_str(), Some("mno"));
+        });
+        assert_eq!(stu.statement().unwrap().signature[0].word_str(), Some("stu"));
+        assert_eq!(stu.docs[0].text, "Kept.");
+    });
+}
+
+#[test]
+fn legacy_comment_prefixes() {
+    let (tree, _) = parse(&normalize("
+        |server:
+        |;--- net ---
+        |  port 80
+        |;{ note }
+        |  host
+    ")).unwrap();
+
+    assert_matches!(&tree[..], [server] => {
+        assert_matches!(server.children(), [port, host] => {
+            assert_eq!(port.statement().unwrap().signature[0].word_str(), Some("port"));
+            assert_eq!(host.statement().unwrap().signature[0].word_str(), Some("host"));
+        });
+    });
+}
+
+#[test]
+fn trailing_comments() {
+    let content = normalize("
+        |server: main ; primary
+        |  port 8080 ;; default
+        |  tls: on;
+        |  ;; Not trailing.
+        |  host
+    ");
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.as_str().into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2)).trailing_comments(true);
+    let tree = Tree::parse_with(map.input(index), options).unwrap();
+
+    assert_matches!(&tree[..], [server] => {
+        let comment = server.comment.as_ref().unwrap();
+        assert_eq!(comment.text, "primary");
+        assert_eq!(map.span_str(comment.location), "; primary");
+        assert_matches!(server.children(), [port, tls, host] => {
+            assert_eq!(port.comment.as_ref().unwrap().text, "; default");
+            assert_eq!(port.statement().unwrap().signature.len(), 2);
+            assert_eq!(tls.comment.as_ref().unwrap().text, "");
+            assert_eq!(host.docs.len(), 1);
+            assert!(host.comment.is_none());
+        });
+    });
+
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    assert!(tree[0].comment.is_none());
+}
+
 #[test]
 fn words() {
     for value in ["a", "a_b", "a-b", "$a$", "a.b", "a23", "+", "&", "/"] {
@@ -311,3 +420,290 @@ fn indents() {
     assert_matches!(Indent::try_spaces(0), None);
     assert_matches!(Indent::try_spaces(2), Some(_));
 }
+
+#[test]
+fn locations() {
+    let content = normalize("
+        |abc:
+        |  def (ghi)
+    ");
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.as_str().into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    let mut locator = Locator::new(map.input(index));
+
+    assert_matches!(&tree[..], [node_abc] => {
+        assert_eq!(locator.byte_index(node_abc.location), Some(0));
+        assert_matches!(node_abc.children(), [node_def] => {
+            let def_index = content.find("def").unwrap();
+            assert_eq!(locator.byte_index(node_def.location), Some(def_index));
+
+            let items = &node_def.statement().unwrap().signature;
+            let ghi_index = content.find("ghi").unwrap();
+            let ghi = &items[1].parenthesized().unwrap()[0];
+            assert_eq!(locator.byte_range(ghi.location), Some(ghi_index..(ghi_index + 3)));
+            assert_eq!(locator.byte_range(items[0].location), Some(def_index..(def_index + 3)));
+        });
+    });
+}
+
+fn parse_limited(content: &str, options: ParseOptions) -> Result<Tree, (ParseError, usize)> {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let mut locator = Locator::new(map.input(index));
+    Tree::parse_with(map.input(index), options).map_err(|error| {
+        (error.error().clone(), locator.byte_index(error.offset()).unwrap())
+    })
+}
+
+#[test]
+fn limits() {
+    let options = ParseOptions::new(Indent::spaces(2));
+    let source = &normalize("
+        |a: (b [c])
+        |  d: e
+        |    f
+    ");
+    assert_matches!(parse_limited(source, options), Ok(_));
+    assert_matches!(
+        parse_limited(source, options.max_line_length(10).max_depth(2).max_group_depth(2)),
+        Ok(_)
+    );
+    assert_matches!(parse_limited(source, options.max_items(5).max_nodes(3)), Ok(_));
+
+    assert_eq!(
+        parse_limited(source, options.max_line_length(9)),
+        Err((ParseError::LineLengthLimit { limit: 9 }, 0)),
+    );
+    assert_eq!(
+        parse_limited(source, options.max_depth(1)),
+        Err((ParseError::DepthLimit { limit: 1 }, 22)),
+    );
+    assert_eq!(
+        parse_limited(source, options.max_group_depth(1)),
+        Err((ParseError::GroupDepthLimit { limit: 1 }, 6)),
+    );
+    assert_eq!(
+        parse_limited(source, options.max_items(4)),
+        Err((ParseError::ItemLimit { limit: 4 }, 7)),
+    );
+    assert_eq!(
+        parse_limited(source, options.max_nodes(2)),
+        Err((ParseError::NodeLimit { limit: 2 }, 22)),
+    );
+}
+
+#[test]
+fn deeply_nested_groups() {
+    let depth = 5_000;
+    let content = format!("a {}{}", "(".repeat(depth), ")".repeat(depth));
+    let tree = parse_limited(&content, ParseOptions::new(Indent::spaces(2))).unwrap();
+    let mut item = &tree[0].statement().unwrap().signature[1];
+    for _ in 1..depth {
+        item = &item.parenthesized().unwrap()[0];
+    }
+    assert_eq!(item.parenthesized().map(<[_]>::len), Some(0));
+
+    let unclosed = format!("a {}", "[".repeat(depth));
+    assert_eq!(
+        parse_limited(&unclosed, ParseOptions::new(Indent::spaces(2))),
+        Err((ParseError::UnclosedGroup { missing: ']' }, 2 + depth - 1)),
+    );
+    assert_eq!(
+        parse_limited(&unclosed, ParseOptions::new(Indent::spaces(2)).max_group_depth(64)),
+        Err((ParseError::GroupDepthLimit { limit: 64 }, 2 + 64)),
+    );
+}
+
+#[test]
+fn doc_comments() {
+    let content = normalize("
+        |;; Main server.
+        |;;
+        |; not documentation
+        |server: main
+        |  ;;  Port number.
+        |  port 80 ;; trailing
+        |  tls: on
+        |;; dangling
+    ");
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.as_str().into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+
+    assert_matches!(&tree[..], [server] => {
+        let docs: Vec<_> = server.docs.iter().map(|doc| doc.text.as_str()).collect();
+        assert_eq!(docs, ["Main server.", ""]);
+        assert_eq!(map.span_str(server.docs[0].location), ";; Main server.");
+        assert_matches!(server.children(), [port, tls] => {
+            assert_eq!(port.docs.len(), 1);
+            assert_eq!(port.docs[0].text, " Port number.");
+            assert_eq!(map.span_str(port.docs[0].location), ";;  Port number.");
+            assert_eq!(port.statement().unwrap().signature.len(), 2);
+            assert!(tls.docs.is_empty());
+        });
+    });
+}
+
+#[test]
+fn pairs() {
+    let content = "server timeout=30 ratio=-0.5 url=a.b list=(1 x=2) flag=";
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2)).pair_separator('=');
+    let error = Tree::parse_with(map.input(index), options).unwrap_err().into_error();
+    assert_eq!(error, ParseError::DanglingSeparator { separator: '=' });
+
+    let content = &content[..content.len() - " flag=".len()];
+    let index = map.insert(Origin::from_named("test-source-2"), content.into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse_with(map.input(index), options).unwrap();
+    let item = assert_tree_test_items!(&tree, [_, timeout, ratio, url, list] => {
+        assert_matches!(timeout.pair(), Some((key, value)) => {
+            assert_eq!(key.word_str(), Some("timeout"));
+            assert_eq!(map.span_str(key.location), "timeout");
+            assert_eq!(value.int(), Some(30));
+            assert_eq!(map.span_str(value.location), "30");
+        });
+        assert_eq!(map.span_str(timeout.location), "timeout=");
+        assert_eq!(ratio.pair().unwrap().1.float(), Some(-0.5));
+        assert_eq!(url.pair().unwrap().1.word_str(), Some("a.b"));
+        list.clone()
+    });
+    assert_matches!(item.pair(), Some((_, value)) => {
+        assert_matches!(value.parenthesized(), Some([one, pair]) => {
+            assert_eq!(one.int(), Some(1));
+            assert!(pair.is_pair());
+        });
+    });
+    assert_eq!(item.to_string(), "list=(1 x=2)");
+
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    assert_tree_test_items!(&tree, [_, timeout, ..] => {
+        assert_eq!(timeout.word_str(), Some("timeout=30"));
+    });
+}
+
+#[test]
+fn dangling_pair_separators() {
+    let options = ParseOptions::new(Indent::spaces(2)).pair_separator('=');
+    let dangling = ParseError::DanglingSeparator { separator: '=' };
+    assert_eq!(parse_limited("a =1", options), Err((dangling.clone(), 2)));
+    assert_eq!(parse_limited("a b= (1)", options), Err((dangling.clone(), 3)));
+    assert_eq!(parse_limited("a (b=)", options), Err((dangling.clone(), 4)));
+    assert_eq!(parse_limited("a b=: c", options), Err((dangling, 3)));
+    assert!(parse_limited("a b={c}: d=[e]", options).is_ok());
+}
+
+#[test]
+fn repeated_pair_separators() {
+    let options = ParseOptions::new(Indent::spaces(2)).pair_separator('=');
+    let repeated = ParseError::RepeatedSeparator { separator: '=' };
+    assert_eq!(parse_limited("a b=c=", options), Err((repeated.clone(), 5)));
+    assert_eq!(parse_limited("a b==1", options), Err((repeated.clone(), 4)));
+    assert_eq!(parse_limited("a b=c=d", options), Err((repeated.clone(), 5)));
+    assert_eq!(parse_limited("a b==(1)", options), Err((repeated, 4)));
+}
+
+#[test]
+fn invalid_pair_keys() {
+    let options = ParseOptions::new(Indent::spaces(2)).pair_separator('=');
+    let int = |value: &str| ParseError::InvalidInt { value: value.into() };
+    assert_eq!(parse_limited("a 1=2", options), Err((int("1=2"), 2)));
+    assert_eq!(parse_limited("a -x=(y)", options), Err((int("-x="), 2)));
+    let key = ParseError::InvalidPairKey { key: "__END__".into() };
+    assert_eq!(parse_limited("a __END__=1", options), Err((key, 2)));
+    let options = ParseOptions::new(Indent::spaces(2)).pair_separator('.');
+    assert!(parse_limited("a 1.5 b.c", options).is_ok());
+}
+
+#[test]
+fn literals() {
+    let content = "test true false null yes (no) none=none True";
+    let options = ParseOptions::new(Indent::spaces(2)).pair_separator('=');
+    let tree = parse_limited(content, options.literals(Literals::DEFAULT)).unwrap();
+    assert_tree_test_items!(&tree, [_, t, f, n, yes, _, _, upper] => {
+        assert_eq!(t.bool(), Some(true));
+        assert_eq!(f.bool(), Some(false));
+        assert!(n.is_null());
+        assert!(yes.is_word());
+        assert!(upper.is_word());
+    });
+
+    const YES_NO: Literals = Literals::new(&["true", "yes"], &["false", "no"], &["none"]);
+    let tree = parse_limited(content, options.literals(YES_NO)).unwrap();
+    assert_tree_test_items!(&tree, [_, _, _, null, yes, no, pair, _] => {
+        assert!(null.is_word());
+        assert_eq!(yes.bool(), Some(true));
+        assert_eq!(no.parenthesized().unwrap()[0].bool(), Some(false));
+        assert_matches!(pair.pair(), Some((key, value)) => {
+            assert_eq!(key.word_str(), Some("none"));
+            assert!(value.is_null());
+        });
+    });
+
+    let tree = parse_limited(content, options).unwrap();
+    assert_tree_test_items!(&tree, [_, t, ..] => assert!(t.is_word()));
+}
+
+#[test]
+fn sigils() {
+    let content = "test $var @ref #tag $ a$b true";
+    let options = ParseOptions::new(Indent::spaces(2))
+        .sigils(&['$', '@', '#'])
+        .literals(Literals::DEFAULT);
+    let tree = parse_limited(content, options).unwrap();
+    assert_tree_test_items!(&tree, [_, var, reference, tag, sigil, word, literal] => {
+        assert_eq!(var.kind, ItemKind::Sigil { sigil: '$', name: "var".into() });
+        assert_eq!(var.sigil_name('$'), Some("var"));
+        assert_eq!(reference.sigil_name('@'), Some("ref"));
+        assert_eq!(reference.sigil_name('$'), None);
+        assert_eq!(tag.to_string(), "#tag");
+        assert_eq!(sigil.word_str(), Some("$"));
+        assert_eq!(word.word_str(), Some("a$b"));
+        assert!(literal.is_bool());
+    });
+
+    let tree = parse_limited(content, ParseOptions::new(Indent::spaces(2))).unwrap();
+    assert_tree_test_items!(&tree, [_, var, ..] => {
+        assert_eq!(var.word_str(), Some("$var"));
+        assert_eq!(var.sigil_name('$'), None);
+    });
+}
+
+#[test]
+#[should_panic(expected = "invalid sigil")]
+fn structural_sigil() {
+    let _ = ParseOptions::new(Indent::spaces(2)).sigils(&['$', ':']);
+}
+
+#[test]
+#[should_panic(expected = "invalid sigil")]
+fn number_sigil() {
+    let _ = ParseOptions::new(Indent::spaces(2)).sigils(&['-']);
+}
+
+#[test]
+#[should_panic(expected = "invalid pair separator")]
+fn whitespace_pair_separator() {
+    let _ = ParseOptions::new(Indent::spaces(2)).pair_separator(' ');
+}
+
+#[test]
+#[should_panic(expected = "invalid pair separator")]
+fn structural_pair_separator() {
+    let _ = ParseOptions::new(Indent::spaces(2)).pair_separator('(');
+}
+
+#[test]
+fn try_options() {
+    let options = ParseOptions::new(Indent::spaces(2));
+    assert_eq!(options.try_sigils(&['$', ':']), None);
+    assert_eq!(options.try_sigils(&['$']), Some(options.sigils(&['$'])));
+    assert_eq!(options.try_pair_separator('1'), None);
+    assert_eq!(options.try_pair_separator('='), Some(options.pair_separator('=')));
+}
@@ -0,0 +1,95 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, Item, ItemKind, ParseOptions, Literals};
+
+
+fn parse(content: &str, indent: Indent) -> Tree {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    Tree::parse(map.input(index), indent).unwrap()
+}
+
+fn items(tree: &Tree) -> Vec<Item> {
+    tree.iter().flat_map(|node| node.statement().unwrap().signature.clone()).collect()
+}
+
+#[test]
+fn sources() {
+    let source = normalize("
+        |abc  def:   (1 2.5) ;comment
+        |  ghi [jkl {-3}]
+        |  mno:
+        |
+        |    pqr:
+    ");
+    let printed = parse(&source, Indent::spaces(2)).to_source(Indent::tabs());
+    assert_eq!(printed, "abc def: (1 2.5)\n\tghi [jkl {-3}]\n\tmno:\n\t\tpqr:\n");
+
+    let reprinted = parse(&printed, Indent::tabs()).to_source(Indent::spaces(2));
+    assert_eq!(reprinted, "abc def: (1 2.5)\n  ghi [jkl {-3}]\n  mno:\n    pqr:\n");
+
+    assert_eq!(parse("", Indent::tabs()).to_source(Indent::tabs()), "");
+}
+
+#[test]
+fn doc_comments() {
+    let source = "  ;; Docs.\nabc:\n  ;;\n  ;;  def\n  def\n";
+    let printed = parse(source, Indent::spaces(2)).to_source(Indent::tabs());
+    assert_eq!(printed, ";; Docs.\nabc:\n\t;;\n\t;;  def\n\tdef\n");
+    assert_eq!(parse(&printed, Indent::tabs()).to_source(Indent::spaces(2)), source.trim_start());
+}
+
+#[test]
+fn trailing_comments() {
+    let source = "abc: 1 ;  one\n  def ;\n  ghi\n";
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), source.into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2)).trailing_comments(true);
+    let tree = Tree::parse_with(map.input(index), options).unwrap();
+    assert_eq!(tree.to_source(Indent::spaces(2)), "abc: 1 ;  one\n  def ;\n  ghi\n");
+}
+
+#[test]
+fn pairs() {
+    let source = "abc: a/1 b/(c d/[2.5]) g=h\n  e/f\n";
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), source.into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2)).pair_separator('/');
+    let tree = Tree::parse_with(map.input(index), options).unwrap();
+    assert_eq!(tree.to_source(Indent::spaces(2)), source);
+}
+
+#[test]
+fn literals() {
+    const YES_NO: Literals = Literals::new(&["yes"], &["no"], &["none"]);
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), "abc yes no none\n".into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2));
+    let tree = Tree::parse_with(map.input(index), options.literals(YES_NO)).unwrap();
+    let printed = tree.to_source(Indent::spaces(2));
+    assert_eq!(printed, "abc true false null\n");
+
+    let index = map.insert(Origin::from_named("printed"), printed.into())
+        .try_into_inserted().unwrap();
+    let reparsed = Tree::parse_with(map.input(index), options.literals(Literals::DEFAULT))
+        .unwrap();
+    let kinds = |tree: &Tree| items(tree).into_iter().map(|item| item.kind).collect::<Vec<_>>();
+    assert_eq!(kinds(&reparsed), kinds(&tree));
+    let reparsed = Tree::parse_with(map.input(index), options.literals(YES_NO)).unwrap();
+    assert!(items(&reparsed)[1].is_word());
+}
+
+#[test]
+fn items_display() {
+    let tree = parse("test 23 -0.5 23.0 a.b () [x] {y z}", Indent::tabs());
+    let displayed = items(&tree).iter().map(|item| item.to_string()).collect::<Vec<_>>();
+    assert_eq!(displayed, ["test", "23", "-0.5", "23.0", "a.b", "()", "[x]", "{y z}"]);
+
+    assert_eq!(ItemKind::Float(1e20).to_string(), "100000000000000000000.0");
+    assert_eq!(ItemKind::Float(-2.0).to_string(), "-2.0");
+    assert_eq!(ItemKind::Bool(false).to_string(), "false");
+    assert_eq!(ItemKind::Null.to_string(), "null");
+}
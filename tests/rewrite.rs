@@ -0,0 +1,169 @@
+use src_ctx::{SourceMap, Origin};
+use treelang::{Indent, Tree, TextEdit, ParseOptions, Literals};
+use treelang::rewrite::{Rewriter, RewriteError};
+use assert_matches::assert_matches;
+
+
+const SOURCE: &str = concat!(
+    "; settings\n",
+    "server: main\n",
+    "  port 80 ; default\n",
+    "  tls: (on [cert key])\n",
+    "    file a.pem\n",
+    "client: x",
+);
+
+fn parse(content: &str) -> (Tree, SourceMap) {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    (tree, map)
+}
+
+fn rewrite<F>(content: &str, change: F) -> (String, Vec<TextEdit>)
+where
+    F: FnOnce(&Tree, &Tree, &mut Rewriter<'_>),
+{
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let extra = map.insert(Origin::from_named("extra"), "new: 1\n  child\n".into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    let extra = Tree::parse(map.input(extra), Indent::spaces(2)).unwrap();
+    let mut rewriter = Rewriter::new(map.input(index), Indent::spaces(2));
+    change(&tree, &extra, &mut rewriter);
+    let source = rewriter.to_source();
+    (source, rewriter.into_edits())
+}
+
+#[test]
+fn rename_word() {
+    let (output, edits) = rewrite(SOURCE, |tree, _, rewriter| {
+        let port = &tree[0].children()[0].statement().unwrap().signature[0];
+        rewriter.rename_word(port, "listen").unwrap();
+    });
+    let start = SOURCE.find("port").unwrap();
+    assert_eq!(edits, [TextEdit::new(start..(start + 4), "listen")]);
+    assert_eq!(output, SOURCE.replace("port 80", "listen 80"));
+}
+
+#[test]
+fn replace_group() {
+    let (output, _) = rewrite(SOURCE, |tree, _, rewriter| {
+        let tls = tree[0].children()[1].directive().unwrap();
+        let brackets = &tls.arguments[0].parenthesized().unwrap()[1];
+        let error = rewriter.rename_word(brackets, "other").unwrap_err();
+        assert_eq!(error.error(), &RewriteError::ExpectedWord);
+        rewriter.replace_item(&tls.arguments[0], &tls.signature[0]).unwrap();
+        let error = rewriter.replace_item(brackets, &tls.signature[0]).unwrap_err();
+        assert_eq!(error.error(), &RewriteError::Overlapping);
+    });
+    assert_eq!(output, SOURCE.replace("(on [cert key])", "tls"));
+}
+
+#[test]
+fn remove_node() {
+    let (output, _) = rewrite(SOURCE, |tree, _, rewriter| {
+        rewriter.remove_node(&tree[0].children()[1]).unwrap();
+        rewriter.remove_node(&tree[1]).unwrap();
+    });
+    assert_eq!(output, "; settings\nserver: main\n  port 80 ; default\n");
+}
+
+#[test]
+fn insert_child() {
+    let (output, _) = rewrite(SOURCE, |tree, extra, rewriter| {
+        rewriter.insert_child(&tree[0], 1, &extra[0]).unwrap();
+        rewriter.insert_child(&tree[0].children()[1], 1, &extra[0].children()[0]).unwrap();
+        rewriter.insert_child(&tree[1], 0, &extra[0].children()[0]).unwrap();
+    });
+    assert_eq!(output, concat!(
+        "; settings\n",
+        "server: main\n",
+        "  port 80 ; default\n",
+        "  new: 1\n",
+        "    child\n",
+        "  tls: (on [cert key])\n",
+        "    file a.pem\n",
+        "    child\n",
+        "client: x\n",
+        "  child",
+    ));
+    let (tree, _) = parse(&output);
+    assert_eq!(tree[0].children().len(), 3);
+}
+
+#[test]
+fn documented_nodes() {
+    let source = concat!(
+        "server: main\n",
+        "  ;; The port.\n",
+        "  port 80\n",
+        "  ;; Secure connections.\n",
+        "  ;; Needs a certificate.\n",
+        "  tls on\n",
+    );
+    let (output, _) = rewrite(source, |tree, _, rewriter| {
+        assert_eq!(tree[0].children()[1].docs.len(), 2);
+        rewriter.remove_node(&tree[0].children()[1]).unwrap();
+    });
+    assert_eq!(output, "server: main\n  ;; The port.\n  port 80\n");
+
+    let (output, _) = rewrite(source, |tree, extra, rewriter| {
+        rewriter.insert_child(&tree[0], 1, &extra[0].children()[0]).unwrap();
+    });
+    assert_eq!(output, source.replace("  ;; Secure", "  child\n  ;; Secure"));
+    let (tree, _) = parse(&output);
+    assert_eq!(tree[0].children()[2].docs.len(), 2);
+}
+
+#[test]
+fn rename_with_options() {
+    let options = ParseOptions::new(Indent::spaces(2))
+        .pair_separator('=')
+        .literals(Literals::DEFAULT)
+        .sigils(&['$']);
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), "port 80 a=b".into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse_with(map.input(index), options).unwrap();
+    let port = &tree[0].statement().unwrap().signature[0];
+
+    let mut rewriter = Rewriter::with_options(map.input(index), options);
+    for word in ["true", "null", "$x", "a=b"] {
+        let error = rewriter.rename_word(port, word).unwrap_err();
+        assert_eq!(error.error(), &RewriteError::InvalidWord { word: word.into() });
+    }
+    rewriter.rename_word(port, "$").unwrap();
+    assert_eq!(rewriter.to_source(), "$ 80 a=b");
+
+    let mut rewriter = Rewriter::new(map.input(index), Indent::spaces(2));
+    rewriter.rename_word(port, "true").unwrap();
+    assert_eq!(rewriter.to_source(), "true 80 a=b");
+}
+
+#[test]
+fn errors() {
+    rewrite(SOURCE, |tree, extra, rewriter| {
+        let port = &tree[0].children()[0];
+        let error = rewriter.insert_child(port, 0, &extra[0]).unwrap_err();
+        assert_eq!(error.error(), &RewriteError::ExpectedDirective);
+
+        let error = rewriter.insert_child(&tree[1], 1, &extra[0]).unwrap_err();
+        assert_eq!(error.error(), &RewriteError::InvalidPosition { position: 1 });
+
+        let word = &port.statement().unwrap().signature[0];
+        let error = rewriter.rename_word(word, "a b").unwrap_err();
+        assert_matches!(error.error(), RewriteError::InvalidWord { word } if word == "a b");
+
+        let error = rewriter.rename_word(&extra[0].directive().unwrap().signature[0], "a")
+            .unwrap_err();
+        assert_eq!(error.error(), &RewriteError::UnknownLocation);
+
+        rewriter.remove_node(&tree[0]).unwrap();
+        let error = rewriter.rename_word(word, "listen").unwrap_err();
+        assert_eq!(error.error(), &RewriteError::Overlapping);
+    });
+}
@@ -0,0 +1,149 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, ParseOptions, Literals};
+use treelang::sexpr::{Sexpr, SexprError};
+use assert_matches::assert_matches;
+
+
+fn parse(content: &str) -> Tree {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    Tree::parse(map.input(index), Indent::spaces(2)).unwrap()
+}
+
+#[test]
+fn trees() {
+    let tree = parse(&normalize("
+        |abc def: 23 -0.5
+        |  ghi (jkl [1.0] {})
+        |  mno:
+    "));
+    assert_eq!(
+        tree.to_sexpr().to_string(),
+        "(((abc def) (23 -0.5) (((ghi (parentheses jkl (brackets 1.0) (braces)))) ((mno) () ()))))",
+    );
+    assert_eq!(format!("{:#}", tree.to_sexpr()), normalize("
+        |(((abc def)
+        |  (23 -0.5)
+        |  (((ghi
+        |     (parentheses jkl
+        |      (brackets 1.0)
+        |      (braces))))
+        |   ((mno)
+        |    ()
+        |    ()))))
+    ").trim());
+
+    assert_eq!(parse("").to_sexpr().to_string(), "()");
+    assert_eq!(parse("a\"b c'd").to_sexpr().to_string(), r#"((("a\"b" "c'd")))"#);
+}
+
+#[test]
+fn parsing() {
+    let value = Sexpr::parse(r#" (a "b \" c" ; comment
+        (-1 2.5 -) ()) "#).unwrap();
+    assert_eq!(value, Sexpr::List(vec![
+        Sexpr::Word("a".into()),
+        Sexpr::Word("b \" c".into()),
+        Sexpr::List(vec![Sexpr::Int(-1), Sexpr::Float(2.5), Sexpr::Word("-".into())]),
+        Sexpr::List(vec![]),
+    ]));
+
+    let tree = parse("abc: (def \"x\" 2.0)\n  ghi");
+    assert_eq!(Sexpr::parse(&tree.to_sexpr().to_string()), Ok(tree.to_sexpr()));
+    assert_eq!(Sexpr::parse(&format!("{:#}", tree.to_sexpr())), Ok(tree.to_sexpr()));
+
+    assert_eq!(Sexpr::parse("(a"), Err(SexprError::UnexpectedEnd));
+    assert_eq!(Sexpr::parse("\"a"), Err(SexprError::UnexpectedEnd));
+    assert_eq!(Sexpr::parse("a)"), Err(SexprError::UnexpectedChar { unexpected: ')', index: 1 }));
+    assert_matches!(Sexpr::parse("(1a)"), Err(SexprError::InvalidNumber { value, index: 1 }) => {
+        assert_eq!(value, "1a");
+    });
+
+    let nested = |depth: usize| format!("{}{}", "(".repeat(depth), ")".repeat(depth));
+    assert!(Sexpr::parse(&nested(Sexpr::MAX_DEPTH)).is_ok());
+    assert_eq!(
+        Sexpr::parse(&nested(Sexpr::MAX_DEPTH + 1)),
+        Err(SexprError::DepthLimit { limit: Sexpr::MAX_DEPTH, index: Sexpr::MAX_DEPTH }),
+    );
+    assert_eq!(Sexpr::parse(&"(".repeat(1_000_000)).map(|_| ()), Err(SexprError::DepthLimit {
+        limit: Sexpr::MAX_DEPTH,
+        index: Sexpr::MAX_DEPTH,
+    }));
+}
+
+#[test]
+fn sources() {
+    let tree = parse(&normalize("
+        |abc def: 23 -0.5
+        |  ghi (jkl [1.0] {})
+        |  mno:
+    "));
+    let source = tree.to_sexpr().to_source(Indent::tabs()).unwrap();
+    assert_eq!(source, "abc def: 23 -0.5\n\tghi (jkl [1.0] {})\n\tmno:\n");
+    assert_eq!(parse(&tree.to_source(Indent::spaces(2))).to_sexpr(), tree.to_sexpr());
+
+    let to_source = |input: &str| Sexpr::parse(input).unwrap().to_source(Indent::tabs());
+    assert_eq!(to_source("(((a) () (((b c)))))"), Ok("a:\n\tb c\n".into()));
+    assert_eq!(to_source("a"), Err(SexprError::ExpectedList));
+    assert_eq!(to_source("(a)"), Err(SexprError::ExpectedNode));
+    assert_eq!(to_source("((() ()))"), Err(SexprError::ExpectedNode));
+    assert_eq!(to_source("((()))"), Err(SexprError::EmptySignature));
+    assert_eq!(to_source("(((a (other b))))"), Err(SexprError::ExpectedItem));
+    assert_eq!(to_source("((((parentheses a)) a ()))"), Err(SexprError::ExpectedList));
+    assert_matches!(to_source(r#"((("a b")))"#), Err(SexprError::InvalidWord { value }) => {
+        assert_eq!(value, "a b");
+    });
+}
+
+#[test]
+fn pairs() {
+    let to_source = |input: &str| Sexpr::parse(input).unwrap().to_source(Indent::tabs());
+    assert_eq!(
+        to_source("(((a (pair b = 1) (pair c / (brackets d)))))"),
+        Ok("a b=1 c/[d]\n".into()),
+    );
+    assert_eq!(to_source("(((a (pair b == 1))))"), Err(SexprError::ExpectedItem));
+    assert_matches!(to_source("(((a (pair b=c = 1))))"), Err(SexprError::InvalidWord { value }) => {
+        assert_eq!(value, "b=c");
+    });
+}
+
+#[test]
+fn round_trip() {
+    let options = ParseOptions::new(Indent::spaces(2))
+        .literals(Literals::DEFAULT)
+        .sigils(&['$'])
+        .trailing_comments(true);
+    let parse_with = |content: &str| {
+        let mut map = SourceMap::new();
+        let index = map.insert(Origin::from_named("test-source"), content.into())
+            .try_into_inserted().unwrap();
+        Tree::parse_with(map.input(index), options).unwrap()
+    };
+    let tree = parse_with(&normalize("
+        |;; doc
+        |;;
+        |abc true: null $x ; comment
+        |  def false ;
+    "));
+    assert_eq!(
+        tree.to_sexpr().to_string(),
+        "(((abc (bool true)) ((null) (sigil $ x)) (((def (bool false)) :comment \"\")) \
+            :docs (doc \"\") :comment comment))",
+    );
+    let source = tree.to_sexpr().to_source(Indent::spaces(2)).unwrap();
+    assert_eq!(source, tree.to_source(Indent::spaces(2)));
+    assert_eq!(parse_with(&source).to_sexpr(), tree.to_sexpr());
+
+    let to_source = |input: &str| Sexpr::parse(input).unwrap().to_source(Indent::tabs());
+    assert_eq!(to_source("(((a) :comment b :docs (c)))"), Ok(";; c\na ; b\n".into()));
+    assert_eq!(to_source("(((a) :docs c))"), Err(SexprError::ExpectedNode));
+    assert_eq!(to_source("(((a) :comment))"), Err(SexprError::ExpectedNode));
+    assert_eq!(to_source("(((a) :comment \"b\nc\"))"), Err(SexprError::InvalidComment));
+    assert_eq!(to_source("(((a (bool yes))))"), Err(SexprError::ExpectedItem));
+    assert_eq!(to_source("(((a (sigil $$ b))))"), Err(SexprError::ExpectedItem));
+    assert_matches!(to_source("(((a (sigil $ \"\"))))"), Err(SexprError::InvalidWord { value }) => {
+        assert_eq!(value, "$");
+    });
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use src_ctx::{SourceMap, Origin};
+use treelang::{Indent, Tree, ItemKind};
+use treelang::structural::{Structural, StructuralEq};
+
+
+fn parse(map: &mut SourceMap, content: &str) -> Tree {
+    static SOURCES: AtomicUsize = AtomicUsize::new(0);
+    let name = format!("test-source-{}", SOURCES.fetch_add(1, Ordering::Relaxed));
+    let index = map.insert(Origin::from_named(name.as_str()), content.into())
+        .try_into_inserted().unwrap();
+    Tree::parse(map.input(index), Indent::spaces(2)).unwrap()
+}
+
+#[test]
+fn equality() {
+    let mut map = SourceMap::new();
+    let a = parse(&mut map, "abc: 1 (2.5)\n  def [x]");
+    let b = parse(&mut map, "\n\nabc:   1 (2.5) ; comment\n  def [x]");
+    let c = parse(&mut map, "abc: 1 (2.5)\n  def [y]");
+    assert_ne!(a, b);
+    assert!(a.structural_eq(&b));
+    assert!(!a.structural_eq(&c));
+    assert!(!a[0].structural_eq(&parse(&mut map, "abc 1 (2.5)")[0]));
+    assert_eq!(a.stable_hash(), b.stable_hash());
+    assert_ne!(a.stable_hash(), c.stable_hash());
+
+    assert!(ItemKind::Float(0.0).structural_eq(&ItemKind::Float(-0.0)));
+    assert!(ItemKind::Float(f32::NAN).structural_eq(&ItemKind::Float(-f32::NAN)));
+    assert!(!ItemKind::Float(1.0).structural_eq(&ItemKind::Int(1)));
+    assert!(!ItemKind::Parentheses(vec![]).structural_eq(&ItemKind::Brackets(vec![])));
+}
+
+#[test]
+fn hashing() {
+    let mut map = SourceMap::new();
+    let mut counts = HashMap::new();
+    for source in ["a 1", "b", "a  1", "a 1 ;x", "a: 1"] {
+        *counts.entry(Structural(parse(&mut map, source))).or_insert(0) += 1;
+    }
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts[&Structural(parse(&mut map, "a 1"))], 3);
+
+    let tree = parse(&mut map, "a 1");
+    assert_eq!(tree.stable_hash(), 0x3d05_4b99_b559_547d);
+    assert_eq!(Structural(&tree[0]), Structural(&parse(&mut map, "a 1")[0]));
+}
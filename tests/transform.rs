@@ -0,0 +1,264 @@
+use src_ctx::{SourceMap, Origin, normalize};
+use treelang::{Indent, Tree, ParseOptions};
+use treelang::transform::{
+    Variables, VariableError, Macros, MacroError, Conditionals, ConditionError,
+};
+use assert_matches::assert_matches;
+
+
+fn parse(content: &str) -> (Tree, SourceMap) {
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse(map.input(index), Indent::spaces(2)).unwrap();
+    (tree, map)
+}
+
+#[test]
+fn variables() {
+    let (tree, map) = parse(&normalize("
+        |let size: 23 px
+        |box $size:
+        |  let size: 5
+        |  let inner: [$size]
+        |  child $inner ($size {$size})
+        |other: $size
+    "));
+    let substituted = Variables::new("let").allow_shadowing(true).apply(&tree).unwrap();
+    assert_eq!(
+        substituted.to_source(Indent::spaces(2)),
+        "box 23 px:\n  child [5] (5 {5})\nother: 23 px\n",
+    );
+
+    let node = substituted[0].directive().unwrap();
+    assert_eq!(map.span_str(node.signature[1].location), "23");
+    assert_eq!(map.span_str(node.signature[2].location), "px");
+}
+
+#[test]
+fn variable_sigils_and_pairs() {
+    let content = "let size: 23\nbox @size $size w=$size\n";
+    let mut map = SourceMap::new();
+    let index = map.insert(Origin::from_named("test-source"), content.into())
+        .try_into_inserted().unwrap();
+    let options = ParseOptions::new(Indent::spaces(2)).sigils(&['$', '@']).pair_separator('=');
+    let tree = Tree::parse_with(map.input(index), options).unwrap();
+    let substituted = Variables::default().apply(&tree).unwrap();
+    assert_eq!(substituted.to_source(Indent::spaces(2)), "box @size 23 w=23\n");
+
+    let content = "let size: 23 px\nbox w=$size\n";
+    let index = map.insert(Origin::from_named("test-source-2"), content.into())
+        .try_into_inserted().unwrap();
+    let tree = Tree::parse_with(map.input(index), options).unwrap();
+    let error = Variables::default().apply(&tree).unwrap_err();
+    assert_eq!(error.error(), &VariableError::InvalidPairValue);
+}
+
+#[test]
+fn variable_errors() {
+    let (tree, map) = parse(&normalize("
+        |let a: 1
+        |test $b
+    "));
+    let error = Variables::default().apply(&tree).unwrap_err();
+    assert_matches!(error.error(), VariableError::Undefined { name } => assert_eq!(name, "b"));
+    assert_eq!(map.byte_offset_on_line(error.offset()), 5);
+
+    let (tree, _) = parse(&normalize("
+        |let a: 1
+        |test:
+        |  let a: 2
+    "));
+    let error = Variables::default().apply(&tree).unwrap_err();
+    assert_matches!(error.error(), VariableError::Shadowed { name } => assert_eq!(name, "a"));
+    assert_eq!(error.offset(), tree[1].children()[0].location);
+
+    let (tree, _) = parse(&normalize("
+        |test: $a
+        |let a: 1
+    "));
+    let error = Variables::default().apply(&tree).unwrap_err();
+    assert_matches!(error.error(), VariableError::Undefined { .. });
+
+    for source in ["let a", "let a:", "let a b: 1", "let (a): 1", "let a: 1\n  b"] {
+        let (tree, _) = parse(source);
+        let error = Variables::default().apply(&tree).unwrap_err();
+        assert_eq!(error.error(), &VariableError::InvalidDefinition);
+    }
+
+    let (tree, _) = parse("test $ %$a");
+    assert_eq!(Variables::new("set").apply(&tree).unwrap(), tree);
+}
+
+#[test]
+fn macros() {
+    let (tree, map) = parse(&normalize("
+        |macro pair key value:
+        |  entry $key: $value
+        |    $children
+        |  marker $$tmp $other
+        |config:
+        |  pair a 1
+        |  pair b (x):
+        |    note
+        |  pair c: [2]
+    "));
+    let expanded = Macros::default().apply(&tree).unwrap();
+    assert_eq!(expanded.to_source(Indent::spaces(2)), concat!(
+        "config:\n",
+        "  entry a: 1\n",
+        "  marker tmp#1 $other\n",
+        "  entry b: (x)\n",
+        "    note\n",
+        "  marker tmp#2 $other\n",
+        "  entry c: [2]\n",
+        "  marker tmp#3 $other\n",
+    ));
+
+    let entry = expanded[0].children()[0].directive().unwrap();
+    assert_eq!(map.span_str(entry.signature[0].location), "entry");
+    assert_eq!(map.span_str(entry.signature[1].location), "a");
+
+    let (tree, _) = parse(&normalize("
+        |macro wrap:
+        |  macro local:
+        |    expanded
+        |  local
+        |  $children
+        |wrap:
+        |  local
+    "));
+    let expanded = Macros::default().apply(&tree).unwrap();
+    assert_eq!(expanded.to_source(Indent::tabs()), "expanded\nlocal\n");
+
+    let (tree, _) = parse("macro m:\n  marker $$tmp\nm\nm\ntmp#1 x#3");
+    let expanded = Macros::default().apply(&tree).unwrap();
+    assert_eq!(
+        expanded.to_source(Indent::tabs()),
+        "marker tmp#2\nmarker tmp#4\ntmp#1 x#3\n",
+    );
+
+    let (tree, _) = parse(&normalize("
+        |macro inner:
+        |  x
+        |macro outer:
+        |  inner
+        |  macro local:
+        |    y
+        |  local
+        |outer
+        |local
+    "));
+    let expanded = Macros::default().apply(&tree).unwrap();
+    assert_eq!(expanded.to_source(Indent::tabs()), "x\ny\nlocal\n");
+}
+
+#[test]
+fn macro_errors() {
+    let (tree, _) = parse("macro m a:\n  x $a\nm");
+    let error = Macros::default().apply(&tree).unwrap_err();
+    let expected = MacroError::ArgumentCount { name: "m".into(), expected: 1, found: 0 };
+    assert_eq!(error.error(), &expected);
+    assert_eq!(error.offset(), tree[1].location);
+
+    let (tree, _) = parse("macro inner a:\n  x\nmacro outer:\n  inner\nouter");
+    let error = Macros::default().apply(&tree).unwrap_err();
+    assert_matches!(error.error(), MacroError::ArgumentCount { .. });
+    assert_eq!(error.offset(), tree[1].children()[0].location);
+
+    let (tree, _) = parse("macro loop:\n  loop\nloop");
+    let error = Macros::default().recursion_limit(4).apply(&tree).unwrap_err();
+    assert_eq!(error.error(), &MacroError::RecursionLimit { limit: 4 });
+    assert_eq!(error.offset(), tree[0].children()[0].location);
+
+    let (tree, _) = parse("macro m:\n  x\nmacro m:\n  y");
+    let error = Macros::default().apply(&tree).unwrap_err();
+    assert_eq!(error.error(), &MacroError::Duplicate { name: "m".into() });
+    assert_eq!(error.offset(), tree[1].location);
+
+    let (tree, _) = parse("macro fan:\n  fan\n  fan\nfan");
+    let error = Macros::default().expansion_limit(10).apply(&tree).unwrap_err();
+    assert_eq!(error.error(), &MacroError::ExpansionLimit { limit: 10 });
+
+    for source in ["macro", "macro m", "macro m: 1", "macro (m):", "macro m (a):", "macro $m:"] {
+        let (tree, _) = parse(source);
+        let error = Macros::default().apply(&tree).unwrap_err();
+        assert_eq!(error.error(), &MacroError::InvalidDeclaration);
+    }
+}
+
+#[test]
+fn macro_call_sites() {
+    let (tree, _) = parse(&normalize("
+        |macro inner:
+        |  x $undefined
+        |macro outer:
+        |  inner
+        |outer
+        |inner
+    "));
+    let (expanded, expansions) = Macros::default().apply_traced(&tree).unwrap();
+    let error = Variables::default().apply(&expanded).unwrap_err();
+    let undefined = &tree[0].children()[0].statement().unwrap().signature[1];
+    assert_eq!(error.offset(), undefined.location.start());
+
+    let inner_call = tree[1].children()[0].location;
+    let (outer_call, second_call) = (tree[2].location, tree[3].location);
+    assert_eq!(expansions.call_sites(error.offset()), [inner_call, outer_call, second_call]);
+    assert_eq!(expansions.call_sites(inner_call), [outer_call]);
+    assert!(expansions.call_sites(outer_call).is_empty());
+
+    let error = expansions.with_context(error);
+    assert_eq!(error.error(), &VariableError::Undefined { name: "undefined".into() });
+}
+
+#[test]
+fn conditionals() {
+    let (tree, map) = parse(&normalize("
+        |server:
+        |  if debug:
+        |    log verbose
+        |  else if release !test:
+        |    log quiet
+        |  else:
+        |    log normal
+        |  if debug:
+        |    if test:
+        |      mock
+        |  port 80
+    "));
+    let apply = |flags: &[&str]| {
+        Conditionals::new().flags(flags.iter().copied()).apply(&tree).unwrap()
+            .to_source(Indent::tabs())
+    };
+    assert_eq!(apply(&[]), "server:\n\tlog normal\n\tport 80\n");
+    assert_eq!(apply(&["debug", "release"]), "server:\n\tlog verbose\n\tport 80\n");
+    assert_eq!(apply(&["release"]), "server:\n\tlog quiet\n\tport 80\n");
+    assert_eq!(apply(&["release", "test"]), "server:\n\tlog normal\n\tport 80\n");
+    assert_eq!(apply(&["debug", "test"]), "server:\n\tlog verbose\n\tmock\n\tport 80\n");
+
+    let pruned = Conditionals::new().flag("debug").apply(&tree).unwrap();
+    let log = &pruned[0].children()[0];
+    assert_eq!(log.location, tree[0].children()[0].children()[0].location);
+    assert_eq!(map.span_str(log.statement().unwrap().signature[1].location), "verbose");
+
+    let (tree, _) = parse("when a:\n  x\notherwise:\n  y");
+    let pruned = Conditionals::new().keywords("when", "otherwise").apply(&tree).unwrap();
+    assert_eq!(pruned.to_source(Indent::tabs()), "y\n");
+}
+
+#[test]
+fn condition_errors() {
+    let error = |source: &str| {
+        let (tree, _) = parse(source);
+        Conditionals::new().apply(&tree).unwrap_err().error().clone()
+    };
+    assert_eq!(error("if a"), ConditionError::ExpectedDirective);
+    assert_eq!(error("if a: b"), ConditionError::UnexpectedArguments);
+    assert_eq!(error("if:"), ConditionError::MissingCondition);
+    assert_eq!(error("if a:\nelse if:"), ConditionError::MissingCondition);
+    assert_eq!(error("if (a):"), ConditionError::InvalidCondition);
+    assert_eq!(error("else:"), ConditionError::UnexpectedElse);
+    assert_eq!(error("if a:\nelse:\nelse:"), ConditionError::UnexpectedElse);
+    assert_eq!(error("if a:\nb\nelse if c:"), ConditionError::UnexpectedElse);
+}